@@ -0,0 +1,386 @@
+//! Rendering several scenes, or variants of scenes where config values are
+//! swept over, one after the other. Variants sharing the same world share the
+//! assets loaded for it and its BVH.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+
+use crate::bounding_boxes::Scene;
+use crate::command_line_interface::{Overrides, RenderOptions};
+use crate::renderer::CancellationToken;
+use crate::world_loader::Config;
+use crate::FastRng;
+
+/// Values to render a variant with for a config value
+#[derive(Clone, Debug)]
+struct Sweep {
+    path: String,
+    values: Vec<String>,
+}
+
+impl Sweep {
+    /// Parses `PATH=FROM..TO:STEPS` or `PATH=[VALUE, ...]`
+    fn parse(text: &str) -> Result<Self> {
+        let (path, values) = text
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Expecting PATH=VALUES, got `{}`", text))?;
+        let values = values.trim();
+
+        let values = if let Some(list) = values.strip_prefix('[') {
+            let list = list
+                .strip_suffix(']')
+                .ok_or_else(|| anyhow!("Expecting a `]` at the end of `{}`", values))?;
+            split_list(list)
+        } else if let Some((from, rest)) = values.split_once("..") {
+            let (to, steps) = rest
+                .split_once(':')
+                .ok_or_else(|| anyhow!("Expecting FROM..TO:STEPS, got `{}`", values))?;
+            let number = |s: &str| {
+                s.trim()
+                    .parse::<f64>()
+                    .with_context(|| format!("Invalid number `{}`", s.trim()))
+            };
+            let (from, to) = (number(from)?, number(to)?);
+            let steps: usize = steps
+                .trim()
+                .parse()
+                .with_context(|| format!("Invalid number of steps `{}`", steps.trim()))?;
+            ensure!(steps > 0, "Expecting at least one step.");
+            (0..steps)
+                .map(|i| match steps {
+                    1 => from,
+                    _ => from + (to - from) * i as f64 / (steps - 1) as f64,
+                })
+                .map(|value| value.to_string())
+                .collect()
+        } else {
+            bail!(
+                "Expecting FROM..TO:STEPS or [VALUE, ...] for `{}`, got `{}`",
+                path,
+                values
+            );
+        };
+        ensure!(!values.is_empty(), "No values to sweep `{}` over.", path);
+
+        Ok(Self {
+            path: path.trim().to_owned(),
+            values,
+        })
+    }
+}
+
+/// Splits a list on the commas which are not inside brackets or strings
+fn split_list(list: &str) -> Vec<String> {
+    let mut items = vec![String::new()];
+    let (mut depth, mut in_string, mut escaped) = (0, false, false);
+    for c in list.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '(' | '[' | '{' if !in_string => depth += 1,
+            ')' | ']' | '}' if !in_string => depth -= 1,
+            ',' if !in_string && depth == 0 => {
+                items.push(String::new());
+                continue;
+            }
+            _ => {}
+        }
+        items.last_mut().unwrap().push(c);
+    }
+    items
+        .into_iter()
+        .map(|item| item.trim().to_owned())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// A config with some of its values swept
+struct Variant {
+    config: Config,
+    name: String,
+    values: Vec<String>,
+    output: PathBuf,
+}
+
+/// Every combination of the values of the sweeps
+fn combinations(sweeps: &[Sweep]) -> Vec<Vec<String>> {
+    sweeps.iter().fold(vec![Vec::new()], |combinations, sweep| {
+        combinations
+            .iter()
+            .flat_map(|combination| {
+                sweep.values.iter().map(move |value| {
+                    let mut combination = combination.clone();
+                    combination.push(value.clone());
+                    combination
+                })
+            })
+            .collect()
+    })
+}
+
+/// Keeps the characters of a value which are safe in a file name
+fn file_name_part(value: &str) -> String {
+    value
+        .chars()
+        .filter(|&c| c != '"' && !c.is_whitespace())
+        .map(|c| match c {
+            c if c.is_alphanumeric() || "+-._".contains(c) => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+fn variants_of_config(
+    config_file: &Path,
+    template: &str,
+    sweeps: &[Sweep],
+    overrides: &Overrides,
+) -> Result<Vec<Variant>> {
+    ensure!(
+        config_file.extension().is_some_and(|s| s == "ron"),
+        "Expecting a .ron config file, got `{}`.",
+        config_file.display()
+    );
+    let mut config = Config::parse(config_file)
+        .with_context(|| format!("Error parsing the config file `{}`", config_file.display()))?;
+    overrides.apply(&mut config)?;
+    let name = config_file
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+
+    let combinations = combinations(sweeps);
+    let digits = (combinations.len() - 1).to_string().len();
+    combinations
+        .into_iter()
+        .enumerate()
+        .map(|(index, values)| {
+            let mut config = config.clone();
+            let mut output = template
+                .replace("{name}", &name)
+                .replace("{index}", &format!("{:0digits$}", index, digits = digits));
+            for (sweep, value) in sweeps.iter().zip(&values) {
+                config.set(&sweep.path, value).with_context(|| {
+                    format!(
+                        "Error setting `{}` to `{}` in `{}`",
+                        sweep.path, value, name
+                    )
+                })?;
+                output = output.replace(&format!("{{{}}}", sweep.path), &file_name_part(value));
+            }
+            Ok(Variant {
+                config,
+                name: name.clone(),
+                values,
+                output: output.into(),
+            })
+        })
+        .collect()
+}
+
+/// How the render of a variant went
+enum Outcome {
+    Rendered(Duration),
+    Stopped(Duration),
+    Failed(String),
+}
+
+/// Renders the variants at `indices`, which share a world, building it only once
+fn render_group(
+    variants: &[Variant],
+    indices: &[usize],
+    cancellation: &CancellationToken,
+    outcomes: &mut [Option<Outcome>],
+) -> Result<()> {
+    let first = &variants[indices[0]];
+    let config = &first.config;
+    let start = Instant::now();
+    let materials = config.materials();
+    let hittables = config.world(&materials)?;
+    let world = Scene::build(&hittables).with_context(|| "Error building the BVH tree")?;
+    println!(
+        "Built the world of {} in {:.1} seconds, for {} variant(s)",
+        first.name,
+        start.elapsed().as_secs_f64(),
+        indices.len()
+    );
+
+    for &index in indices {
+        let variant = &variants[index];
+        if cancellation.is_cancelled() {
+            break;
+        }
+        println!("\nRendering {}", variant.output.display());
+        let options = RenderOptions {
+            output: variant.output.clone(),
+            tree: false,
+            spp_map: None,
+            time_limit: None,
+            preview_interval: None,
+            checkpoint: None,
+            checkpoint_interval: Duration::from_secs(300),
+            resume: None,
+            terminal_preview: None,
+            frames: None,
+        };
+        let rng = FastRng::new(rand::random::<std::num::NonZeroU64>().into());
+        let start = Instant::now();
+        let result = crate::render_scene(&variant.config, &world, &options, cancellation, rng);
+        outcomes[index] = Some(match result {
+            Err(error) => Outcome::Failed(format!("{:#}", error)),
+            Ok(()) if cancellation.is_cancelled() => Outcome::Stopped(start.elapsed()),
+            Ok(()) => Outcome::Rendered(start.elapsed()),
+        });
+    }
+    Ok(())
+}
+
+/// Renders each variant of each config file, and prints a summary
+pub fn batch(
+    config_files: &[PathBuf],
+    template: Option<&str>,
+    sweeps: &[String],
+    overrides: &Overrides,
+    cancellation: &CancellationToken,
+) -> Result<()> {
+    let sweeps = sweeps
+        .iter()
+        .map(|sweep| Sweep::parse(sweep))
+        .collect::<Result<Vec<_>>>()?;
+    let template = template.unwrap_or(if sweeps.is_empty() {
+        "{name}.png"
+    } else {
+        "{name}_{index}.png"
+    });
+
+    let mut variants = Vec::new();
+    for config_file in config_files {
+        variants.extend(variants_of_config(
+            config_file,
+            template,
+            &sweeps,
+            overrides,
+        )?);
+    }
+    for (i, variant) in variants.iter().enumerate() {
+        if let Some(other) = variants[..i].iter().find(|v| v.output == variant.output) {
+            bail!(
+                "Variants of {} and {} would both be saved as `{}`, the output template needs {{index}} or the swept values.",
+                other.name,
+                variant.name,
+                variant.output.display()
+            );
+        }
+    }
+
+    // the variants with the same world, in the order of their first variant. The
+    // worlds are compared as JSON values, whose maps are sorted unlike the
+    // materials of a config.
+    let mut groups: Vec<((&Path, serde_json::Value), Vec<usize>)> = Vec::new();
+    for (index, variant) in variants.iter().enumerate() {
+        let key = (
+            variant.config.directory.as_path(),
+            serde_json::to_value(&variant.config.world)?,
+        );
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, indices)) => indices.push(index),
+            None => groups.push((key, vec![index])),
+        }
+    }
+
+    let mut outcomes: Vec<Option<Outcome>> = variants.iter().map(|_| None).collect();
+    for (_, indices) in &groups {
+        if cancellation.is_cancelled() {
+            break;
+        }
+        if let Err(error) = render_group(&variants, indices, cancellation, &mut outcomes) {
+            println!("Error: {:#}", error);
+            for &i in indices {
+                outcomes[i] = Some(Outcome::Failed(format!("{:#}", error)));
+            }
+        }
+    }
+
+    print_summary(&variants, &sweeps, &outcomes);
+    Ok(())
+}
+
+fn print_summary(variants: &[Variant], sweeps: &[Sweep], outcomes: &[Option<Outcome>]) {
+    let mut header = vec!["Scene".to_owned()];
+    header.extend(sweeps.iter().map(|sweep| sweep.path.clone()));
+    header.extend(["Output".to_owned(), "Time".to_owned()]);
+
+    let rows: Vec<Vec<String>> = variants
+        .iter()
+        .zip(outcomes)
+        .map(|(variant, outcome)| {
+            let mut row = vec![variant.name.clone()];
+            row.extend(variant.values.iter().cloned());
+            row.push(variant.output.display().to_string());
+            row.push(match outcome {
+                Some(Outcome::Rendered(time)) => format!("{:.1}s", time.as_secs_f64()),
+                Some(Outcome::Stopped(time)) => format!("{:.1}s (stopped)", time.as_secs_f64()),
+                Some(Outcome::Failed(error)) => format!("failed: {}", error),
+                None => "skipped".to_owned(),
+            });
+            row
+        })
+        .collect();
+
+    let widths: Vec<usize> = (0..header.len())
+        .map(|column| {
+            std::iter::once(&header)
+                .chain(&rows)
+                .map(|row| row[column].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    println!();
+    for row in std::iter::once(&header).chain(&rows) {
+        let cells: Vec<_> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        println!("{}", cells.join("  ").trim_end());
+    }
+    let total: Duration = outcomes
+        .iter()
+        .map(|outcome| match outcome {
+            Some(Outcome::Rendered(time)) | Some(Outcome::Stopped(time)) => *time,
+            _ => Duration::ZERO,
+        })
+        .sum();
+    println!(
+        "\nRendered {} of {} variant(s) in {:.1} seconds.",
+        outcomes
+            .iter()
+            .filter(|outcome| matches!(outcome, Some(Outcome::Rendered(_))))
+            .count(),
+        variants.len(),
+        total.as_secs_f64()
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::Sweep;
+
+    #[test]
+    fn test_parse_sweep() {
+        let sweep = Sweep::parse("camera.aperture=0..0.5:6").unwrap();
+        assert_eq!(sweep.path, "camera.aperture");
+        assert_eq!(sweep.values, ["0", "0.1", "0.2", "0.3", "0.4", "0.5"]);
+
+        let sweep =
+            Sweep::parse(r#"world.background_color=[Rgb(1, 0, 0), "a,b", Hex(0xFF)]"#).unwrap();
+        assert_eq!(sweep.values, ["Rgb(1, 0, 0)", "\"a,b\"", "Hex(0xFF)"]);
+
+        assert!(Sweep::parse("camera.aperture=0..0.5").is_err());
+    }
+}
@@ -15,6 +15,11 @@ pub struct BoundingBox {
 }
 
 impl BoundingBox {
+    pub const INFINITE: Self = Self {
+        minimum: Point3::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        maximum: Point3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+    };
+
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
         let inv_direction = Point3::ONES / ray.direction;
         let mut t0 = (self.minimum - ray.origin) * inv_direction;
@@ -35,6 +40,23 @@ impl BoundingBox {
         t_max.x > t_min.x && t_max.y > t_min.y && t_max.z > t_min.z
     }
 
+    /// Returns the time interval during which the ray is inside the box
+    pub fn intersect(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+        let inv_direction = Point3::ONES / ray.direction;
+        let t0 = (self.minimum - ray.origin) * inv_direction;
+        let t1 = (self.maximum - ray.origin) * inv_direction;
+
+        let (_, enter) = t0.min(t1).min_max_coords();
+        let (exit, _) = t0.max(t1).min_max_coords();
+        let (enter, exit) = (enter.max(t_min), exit.min(t_max));
+
+        if enter <= exit {
+            Some((enter, exit))
+        } else {
+            None
+        }
+    }
+
     pub fn join(&self, other: &Self) -> Self {
         Self {
             minimum: self.minimum.min(other.minimum),
@@ -42,6 +64,15 @@ impl BoundingBox {
         }
     }
 
+    pub fn is_finite(&self) -> bool {
+        self.minimum.x.is_finite()
+            && self.minimum.y.is_finite()
+            && self.minimum.z.is_finite()
+            && self.maximum.x.is_finite()
+            && self.maximum.y.is_finite()
+            && self.maximum.z.is_finite()
+    }
+
     fn center(&self) -> Point3 {
         (self.minimum + self.maximum) * 0.5
     }
@@ -70,36 +101,39 @@ pub struct BoundingVolumeHierarchy<'a> {
 use SubHierarchy::*;
 
 impl<'a> BoundingVolumeHierarchy<'a> {
+    /// Builds a BVH from all the bounded objects in `world`.
+    /// Unbounded objects are skipped, see [`Scene`].
     pub fn build(world: &'a HittableList<'a>) -> anyhow::Result<Self> {
-        if world.len() == 0 {
+        let mut max = f64::NEG_INFINITY;
+        let mut min: f64 = f64::INFINITY;
+        let mut precomputed_bounding_boxes: Vec<_> = world
+            .iter()
+            .map(|obj| (obj, obj.bounding_box()))
+            .filter(|(_, bbox)| bbox.is_finite())
+            .map(|(obj, bbox)| {
+                let center = bbox.center();
+                let (min_c, max_c) = center.min_max_coords();
+                if min_c < min {
+                    min = min_c - 1e-10;
+                }
+                if max_c > max {
+                    max = max_c + 1e-10;
+                }
+                (obj, bbox, center)
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|(obj, bbox, center)| {
+                (
+                    obj.as_ref(),
+                    bbox,
+                    morton_code((center - min) / (max - min)),
+                )
+            })
+            .collect();
+        if precomputed_bounding_boxes.is_empty() {
             Err(anyhow::anyhow!("Could not create BVH from empty scene."))
         } else {
-            let mut max = f64::NEG_INFINITY;
-            let mut min: f64 = f64::INFINITY;
-            let mut precomputed_bounding_boxes: Vec<_> = world
-                .iter()
-                .map(|obj| {
-                    let bbox = obj.bounding_box();
-                    let center = bbox.center();
-                    let (min_c, max_c) = center.min_max_coords();
-                    if min_c < min {
-                        min = min_c - 1e-10;
-                    }
-                    if max_c > max {
-                        max = max_c + 1e-10;
-                    }
-                    (obj, bbox, center)
-                })
-                .collect::<Vec<_>>()
-                .into_iter()
-                .map(|(obj, bbox, center)| {
-                    (
-                        obj.as_ref(),
-                        bbox,
-                        morton_code((center - min) / (max - min)),
-                    )
-                })
-                .collect();
             precomputed_bounding_boxes.sort_unstable_by_key(|elt| elt.2);
             Ok(Self::from_list(&precomputed_bounding_boxes[..]))
         }
@@ -171,7 +205,7 @@ impl<'a> Hittable for BoundingVolumeHierarchy<'a> {
                 Object(object) => object.hit(ray, t_min, t_max, rng),
                 SubHierarchies { left, right } => {
                     if let Some(left_record) = left.hit(ray, t_min, t_max, rng) {
-                        if let Some(right_record) = right.hit(ray, t_min, left_record.time, rng) {
+                        if let Some(right_record) = right.hit(ray, t_min, left_record.t, rng) {
                             Some(right_record)
                         } else {
                             Some(left_record)
@@ -204,6 +238,86 @@ impl<'a> std::fmt::Display for BoundingVolumeHierarchy<'a> {
     }
 }
 
+/// The top level of the scene: a BVH containing all the bounded objects, and
+/// a list of unbounded objects (such as planes) which are tested separately.
+#[derive(Debug)]
+pub struct Scene<'a> {
+    bvh: Option<BoundingVolumeHierarchy<'a>>,
+    unbounded: Vec<&'a (dyn Hittable + 'a)>,
+}
+
+impl<'a> Scene<'a> {
+    pub fn build(world: &'a HittableList<'a>) -> anyhow::Result<Self> {
+        let unbounded: Vec<_> = world
+            .iter()
+            .filter(|obj| !obj.bounding_box().is_finite())
+            .map(|obj| obj.as_ref())
+            .collect();
+        if world.is_empty() {
+            Err(anyhow::anyhow!("Could not create BVH from empty scene."))
+        } else if unbounded.len() == world.len() {
+            Ok(Self {
+                bvh: None,
+                unbounded,
+            })
+        } else {
+            Ok(Self {
+                bvh: Some(BoundingVolumeHierarchy::build(world)?),
+                unbounded,
+            })
+        }
+    }
+
+    pub fn depth_and_num_nodes(&self) -> (usize, usize) {
+        self.bvh
+            .as_ref()
+            .map(|bvh| bvh.depth_and_num_nodes())
+            .unwrap_or_default()
+    }
+
+    pub fn num_unbounded(&self) -> usize {
+        self.unbounded.len()
+    }
+}
+
+impl<'a> Hittable for Scene<'a> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rng: &mut FastRng) -> Hit {
+        let mut record = self
+            .bvh
+            .as_ref()
+            .and_then(|bvh| bvh.hit(ray, t_min, t_max, rng));
+        let mut closest = record.as_ref().map(|r| r.t).unwrap_or(t_max);
+
+        for object in &self.unbounded {
+            if let Some(r) = object.hit(ray, t_min, closest, rng) {
+                closest = r.t;
+                record = Some(r);
+            }
+        }
+
+        record
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        if !self.unbounded.is_empty() {
+            BoundingBox::INFINITE
+        } else if let Some(bvh) = &self.bvh {
+            bvh.bounding_box()
+        } else {
+            unreachable!()
+        }
+    }
+}
+
+impl<'a> std::fmt::Display for Scene<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if let Some(bvh) = &self.bvh {
+            write!(f, "{}", bvh)?;
+        }
+        write!(f, "\n{} unbounded objects", self.unbounded.len())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::vec3::Vec3;
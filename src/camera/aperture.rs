@@ -0,0 +1,82 @@
+use std::f64::consts::TAU;
+
+use crate::vec3::Vec3;
+
+/// The shape of the lens aperture, which gives its shape to out of focus highlights.
+/// Points are sampled in the square [-1, 1]², scaled by the lens radius afterwards.
+#[derive(Clone, Debug, Default)]
+pub enum Aperture {
+    #[default]
+    Circle,
+    /// A regular polygon inscribed in the unit circle, formed by `blades` blades
+    /// and rotated by `rotation` radians
+    Polygon { blades: u32, rotation: f64 },
+    /// A grayscale mask covering [-1, 1]², where the brightness of each pixel
+    /// is its transmittance
+    Mask {
+        width: usize,
+        height: usize,
+        /// Cumulative distribution of the pixel weights, normalised to end at 1
+        cdf: Vec<f64>,
+    },
+}
+
+impl Aperture {
+    /// Creates a polygonal aperture, which needs at least 3 blades
+    pub fn polygon(blades: u32, rotation: f64) -> Option<Self> {
+        (blades >= 3).then_some(Self::Polygon { blades, rotation })
+    }
+
+    /// Creates a mask aperture from pixel weights stored row by row, top row first
+    pub fn mask(width: usize, height: usize, weights: &[f64]) -> Option<Self> {
+        let mut total = 0.0;
+        let mut cdf: Vec<f64> = weights
+            .iter()
+            .map(|w| {
+                total += w.max(0.0);
+                total
+            })
+            .collect();
+        if total <= 0.0 {
+            return None;
+        }
+        cdf.iter_mut().for_each(|c| *c /= total);
+        Some(Self::Mask { width, height, cdf })
+    }
+
+    /// Maps two uniform numbers in [0, 1) and [0, TAU) to a uniformly distributed
+    /// point of the aperture
+    pub fn sample(&self, r: f64, theta: f64) -> Vec3 {
+        match self {
+            Self::Circle => Vec3::random_in_unit_disk(r, theta),
+            Self::Polygon { blades, rotation } => {
+                // pick one of the triangles fanning out from the center,
+                // and reuse the remainder of `theta` to sample it
+                let sectors = theta / TAU * *blades as f64;
+                let sector = sectors.floor();
+                let h = sectors - sector;
+                let angle = TAU / *blades as f64;
+                let (a0, a1) = (rotation + sector * angle, rotation + (sector + 1.0) * angle);
+                let s = r.sqrt();
+                Vec3::new(
+                    s * (a0.cos() * (1.0 - h) + a1.cos() * h),
+                    s * (a0.sin() * (1.0 - h) + a1.sin() * h),
+                    0.0,
+                )
+            }
+            Self::Mask { width, height, cdf } => {
+                // pick a pixel with `r`, and jitter inside it with the remainder
+                // of `r` and with `theta`
+                let index = cdf.partition_point(|&c| c <= r).min(cdf.len() - 1);
+                let low = if index > 0 { cdf[index - 1] } else { 0.0 };
+                let jitter = ((r - low) / (cdf[index] - low)).clamp(0.0, 1.0);
+                let (i, j) = (index % width, index / width);
+                Vec3::new(
+                    2.0 * (i as f64 + jitter) / *width as f64 - 1.0,
+                    1.0 - 2.0 * (j as f64 + theta / TAU) / *height as f64,
+                    0.0,
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use super::Projection;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+/// A cylindrical panorama covering `horizontal_fov` around the vertical axis.
+/// Vertical lines stay straight, and the vertical scale matches the
+/// horizontal one at the center of the image.
+#[derive(Clone, Debug)]
+pub struct Cylindrical {
+    pub aspect_ratio: f64,
+    pub horizontal_fov: f64,
+}
+
+impl Cylindrical {
+    pub fn new(aspect_ratio: f64, horizontal_fov: f64) -> Self {
+        Self {
+            aspect_ratio,
+            horizontal_fov: horizontal_fov.to_radians(),
+        }
+    }
+}
+
+impl Projection for Cylindrical {
+    fn get_ray(&self, u: f64, v: f64, _: f64, _: f64) -> Option<Ray> {
+        let (sin, cos) = ((u - 0.5) * self.horizontal_fov).sin_cos();
+        let y = (v - 0.5) * self.horizontal_fov / self.aspect_ratio;
+        Some(Ray::new(Vec3::ZEROS, Vec3::new(sin, y, -cos), 0.0))
+    }
+}
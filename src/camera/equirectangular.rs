@@ -0,0 +1,22 @@
+use std::f64::consts::{PI, TAU};
+
+use super::Projection;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+/// A 360° panorama: the longitude varies along the width of the
+/// image and the latitude along its height. Best used with a 2:1 aspect ratio.
+#[derive(Clone, Debug)]
+pub struct Equirectangular;
+
+impl Projection for Equirectangular {
+    fn get_ray(&self, u: f64, v: f64, _: f64, _: f64) -> Option<Ray> {
+        let (sin_phi, cos_phi) = ((u - 0.5) * TAU).sin_cos();
+        let (sin_lat, cos_lat) = ((v - 0.5) * PI).sin_cos();
+        Some(Ray::new(
+            Vec3::ZEROS,
+            Vec3::new(cos_lat * sin_phi, sin_lat, -cos_lat * cos_phi),
+            0.0,
+        ))
+    }
+}
@@ -0,0 +1,39 @@
+use super::Projection;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+/// An equidistant fisheye projection: the angle between a ray and the view
+/// direction is proportional to the distance from the center of the image.
+/// The image circle spans the height of the image.
+#[derive(Clone, Debug)]
+pub struct Fisheye {
+    pub aspect_ratio: f64,
+    pub half_fov: f64,
+}
+
+impl Fisheye {
+    pub fn new(aspect_ratio: f64, fov: f64) -> Self {
+        Self {
+            aspect_ratio,
+            half_fov: 0.5 * fov.to_radians(),
+        }
+    }
+}
+
+impl Projection for Fisheye {
+    fn get_ray(&self, u: f64, v: f64, _: f64, _: f64) -> Option<Ray> {
+        let x = (2.0 * u - 1.0) * self.aspect_ratio;
+        let y = 2.0 * v - 1.0;
+        let r = (x * x + y * y).sqrt();
+        if r > 1.0 {
+            return None;
+        }
+        let (sin, cos) = (r * self.half_fov).sin_cos();
+        let (x, y) = if r > 0.0 { (x / r, y / r) } else { (0.0, 0.0) };
+        Some(Ray::new(
+            Vec3::ZEROS,
+            Vec3::new(sin * x, sin * y, -cos),
+            0.0,
+        ))
+    }
+}
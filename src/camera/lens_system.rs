@@ -0,0 +1,367 @@
+use std::f64::consts::TAU;
+
+use super::Projection;
+use crate::ray::Ray;
+use crate::vec3::{color::Colour, Vec3};
+
+/// Number of samples per axis used to measure the light going through the lens
+const EXPOSURE_SAMPLES: usize = 32;
+
+/// Wavelengths in micrometers of the Fraunhofer lines defining the Abbe number
+const WAVELENGTH_D: f64 = 0.5876;
+const WAVELENGTH_F: f64 = 0.4861;
+const WAVELENGTH_C: f64 = 0.6563;
+/// Wavelengths in micrometers sampled for the red, green and blue channels
+const CHANNEL_WAVELENGTHS: [f64; 3] = [0.61, 0.55, 0.465];
+
+/// A row of a lens prescription table, in millimeters: the curvature radius of
+/// the surface, the distance to the next surface, the refractive index after the
+/// surface and the diameter of the surface. A null radius is the aperture stop,
+/// and a null refractive index stands for air.
+pub type Prescription = (f64, f64, f64, f64);
+
+/// Double Gauss 50mm f/2, US patent 2,673,491 by Tronnier
+pub const DOUBLE_GAUSS_50MM: &[Prescription] = &[
+    (29.475, 3.76, 1.67, 25.2),
+    (84.83, 0.12, 1.0, 25.2),
+    (19.275, 4.025, 1.67, 23.0),
+    (40.77, 3.275, 1.699, 23.0),
+    (12.75, 5.705, 1.0, 18.0),
+    (0.0, 4.5, 0.0, 17.1),
+    (-14.495, 1.18, 1.603, 17.0),
+    (40.77, 6.065, 1.658, 20.0),
+    (-20.385, 0.19, 1.0, 20.0),
+    (437.065, 3.22, 1.717, 20.0),
+    (-39.73, 0.0, 1.0, 20.0),
+];
+
+/// Wide angle 22mm, by Nakamura
+pub const WIDE_ANGLE_22MM: &[Prescription] = &[
+    (35.98738, 1.21638, 1.54, 23.716),
+    (11.69718, 9.9957, 1.0, 17.996),
+    (13.08714, 5.12622, 1.772, 12.364),
+    (-22.63294, 1.76924, 1.617, 9.812),
+    (71.05802, 0.8184, 1.0, 9.152),
+    (0.0, 2.27766, 0.0, 8.756),
+    (-9.58584, 2.43254, 1.617, 8.184),
+    (-11.28864, 0.11506, 1.0, 9.152),
+    (-166.7765, 3.09606, 1.713, 10.648),
+    (-7.5911, 1.32682, 1.805, 11.44),
+    (-16.7662, 3.98068, 1.0, 12.276),
+    (-7.70286, 1.21638, 1.617, 13.42),
+    (-11.97328, 0.0, 1.0, 17.996),
+];
+
+/// Fisheye 10mm, after the 16mm f/4 of Muller
+pub const FISHEYE_10MM: &[Prescription] = &[
+    (30.2249, 0.8335, 1.62, 30.34),
+    (11.3931, 7.4136, 1.0, 20.68),
+    (75.2019, 1.0654, 1.639, 17.8),
+    (8.3349, 11.1549, 1.0, 13.42),
+    (9.5882, 2.0054, 1.654, 9.02),
+    (43.8677, 5.3895, 1.0, 8.14),
+    (0.0, 1.4163, 0.0, 6.08),
+    (29.4541, 2.1934, 1.517, 5.96),
+    (-5.2265, 0.9714, 1.805, 5.84),
+    (-14.2884, 0.0627, 1.0, 5.96),
+    (-22.3726, 0.94, 1.673, 5.96),
+    (-15.0404, 0.0, 1.0, 6.52),
+];
+
+/// Telephoto 250mm f/5.6
+pub const TELEPHOTO_250MM: &[Prescription] = &[
+    (54.6275, 12.52, 1.529, 47.5),
+    (-86.365, 3.755, 1.599, 44.5),
+    (271.7625, 2.8175, 1.0, 41.5),
+    (0.0, 67.4125, 0.0, 40.5),
+    (-32.9425, 3.755, 1.613, 31.5),
+    (49.48, 12.52, 1.603, 33.5),
+    (-50.0825, 0.0, 1.0, 37.0),
+];
+
+#[derive(Clone, Debug)]
+pub struct LensElement {
+    pub curvature_radius: f64,
+    pub thickness: f64,
+    pub ior: f64,
+    pub aperture: f64,
+}
+
+impl From<&Prescription> for LensElement {
+    fn from(&(curvature_radius, thickness, ior, aperture): &Prescription) -> Self {
+        Self {
+            curvature_radius,
+            thickness,
+            ior,
+            aperture,
+        }
+    }
+}
+
+/// A camera tracing rays from the film through a system of spherical lens
+/// elements, listed from the front of the lens to the film.
+///
+/// Lens space is measured in millimeters, with the film at z = 0 and the lens
+/// towards -z. The scene is assumed to be in meters.
+#[derive(Clone, Debug)]
+pub struct LensSystem {
+    pub elements: Vec<LensElement>,
+    /// The z coordinate of each surface
+    pub positions: Vec<f64>,
+    pub film_size: (f64, f64),
+    /// The dispersion of the glass, 0 to disable chromatic aberration
+    pub abbe_number: f64,
+    /// Scales the light reaching the film, so that the center of the image is
+    /// exposed as with an ideal lens
+    pub exposure: f64,
+}
+
+impl LensSystem {
+    /// Creates a lens system focused at `focus_distance` meters from the film, by
+    /// moving the lens with respect to the film. Returns `None` if the lens cannot
+    /// focus at this distance, or if no light goes through it.
+    pub fn new(
+        elements: Vec<LensElement>,
+        aspect_ratio: f64,
+        sensor_height: f64,
+        focus_distance: f64,
+        abbe_number: f64,
+    ) -> Option<Self> {
+        let mut lens = Self {
+            elements,
+            positions: Vec::new(),
+            film_size: (sensor_height * aspect_ratio, sensor_height),
+            abbe_number,
+            exposure: 1.0,
+        };
+        lens.update_positions();
+        lens.focus(focus_distance * 1e3)?;
+
+        let mut transmitted = 0.0;
+        for i in 0..EXPOSURE_SAMPLES {
+            for j in 0..EXPOSURE_SAMPLES {
+                let r = (i as f64 + 0.5) / EXPOSURE_SAMPLES as f64;
+                let theta = TAU * (j as f64 + 0.5) / EXPOSURE_SAMPLES as f64;
+                if let Some((_, weight)) = lens.trace_from_film(0.0, 0.0, r, theta, None) {
+                    transmitted += weight;
+                }
+            }
+        }
+        if transmitted <= 0.0 {
+            return None;
+        }
+        lens.exposure = (EXPOSURE_SAMPLES * EXPOSURE_SAMPLES) as f64 / transmitted;
+        Some(lens)
+    }
+
+    fn update_positions(&mut self) {
+        let mut z = 0.0;
+        self.positions = self
+            .elements
+            .iter()
+            .rev()
+            .map(|element| {
+                z -= element.thickness;
+                z
+            })
+            .collect();
+        self.positions.reverse();
+    }
+
+    /// The refractive index after the element `index` for the given colour channel
+    fn ior(&self, index: usize, channel: Option<usize>) -> f64 {
+        let ior = self.elements[index].ior;
+        if ior <= 1.0 {
+            return 1.0;
+        }
+        match channel {
+            Some(channel) if self.abbe_number > 0.0 => {
+                // Cauchy's equation, fitted to the Abbe number
+                let b = (ior - 1.0)
+                    / (self.abbe_number * (WAVELENGTH_F.powi(-2) - WAVELENGTH_C.powi(-2)));
+                ior + b * (CHANNEL_WAVELENGTHS[channel].powi(-2) - WAVELENGTH_D.powi(-2))
+            }
+            _ => ior,
+        }
+    }
+
+    /// Traces a ray in lens space through the elements, from the film to the
+    /// scene or the other way round. Returns `None` if the ray is blocked.
+    fn trace(&self, mut ray: Ray, from_film: bool, channel: Option<usize>) -> Option<Ray> {
+        let count = self.elements.len();
+        for step in 0..count {
+            let i = if from_film { count - 1 - step } else { step };
+            let element = &self.elements[i];
+            let z = self.positions[i];
+            let before = if i > 0 { self.ior(i - 1, channel) } else { 1.0 };
+            let after = self.ior(i, channel);
+            let (eta_in, eta_out) = if from_film {
+                (after, before)
+            } else {
+                (before, after)
+            };
+
+            let (t, normal) = if element.curvature_radius == 0.0 {
+                (
+                    (z - ray.origin.z) / ray.direction.z,
+                    Vec3::new(0.0, 0.0, -ray.direction.z.signum()),
+                )
+            } else {
+                let center = Vec3::new(0.0, 0.0, z + element.curvature_radius);
+                let oc = ray.origin - center;
+                let a = ray.direction.length_squared();
+                let half_b = oc.dot(ray.direction);
+                let c = oc.length_squared() - element.curvature_radius.powi(2);
+                let delta = half_b * half_b - a * c;
+                if delta < 0.0 {
+                    return None;
+                }
+                let sqrtd = delta.sqrt();
+                let closer = (ray.direction.z > 0.0) ^ (element.curvature_radius < 0.0);
+                let t = if closer {
+                    (-half_b - sqrtd) / a
+                } else {
+                    (-half_b + sqrtd) / a
+                };
+                let normal = (ray.at(t) - center).unit_vector();
+                let normal = if normal.dot(ray.direction) > 0.0 {
+                    -normal
+                } else {
+                    normal
+                };
+                (t, normal)
+            };
+            if t.is_nan() || t <= 0.0 {
+                return None;
+            }
+            let point = ray.at(t);
+            if point.x * point.x + point.y * point.y > 0.25 * element.aperture.powi(2) {
+                return None;
+            }
+            ray.origin = point;
+
+            #[allow(clippy::float_cmp)]
+            if element.curvature_radius != 0.0 && eta_in != eta_out {
+                let direction = ray.direction.unit_vector();
+                let ratio = eta_in / eta_out;
+                let cos_in = -direction.dot(normal);
+                if ratio * ratio * (1.0 - cos_in * cos_in) >= 1.0 {
+                    // total internal reflection
+                    return None;
+                }
+                ray.direction = direction.refract(normal, ratio);
+            }
+        }
+        Some(ray)
+    }
+
+    /// Traces a ray from the film point (x, y) through the point of the rear element
+    /// sampled by `r` and `theta`. Returns the ray leaving the lens, and the weight
+    /// of the light it carries.
+    fn trace_from_film(
+        &self,
+        x: f64,
+        y: f64,
+        r: f64,
+        theta: f64,
+        channel: Option<usize>,
+    ) -> Option<(Ray, f64)> {
+        let rear = self.elements.last()?;
+        let on_rear = Vec3::random_in_unit_disk(r, theta) * (0.5 * rear.aperture);
+        let film = Vec3::new(x, y, 0.0);
+        let direction = Vec3::new(
+            on_rear.x,
+            on_rear.y,
+            self.positions[self.positions.len() - 1],
+        ) - film;
+        // light falls off with the fourth power of the cosine of the angle to the film
+        let cos = direction.z.abs() / direction.length();
+        self.trace(Ray::new(film, direction, 0.0), true, channel)
+            .map(|ray| (ray, cos.powi(4)))
+    }
+
+    /// Computes the focal length, and the positions of the principal planes on the
+    /// scene and on the film sides, by tracing rays parallel to the axis
+    fn thick_lens(&self) -> Option<(f64, f64, f64)> {
+        let height = 1e-3 * self.film_size.0.hypot(self.film_size.1);
+        let front = self.positions[0];
+
+        let from_scene = self.trace(
+            Ray::new(Vec3::new(height, 0.0, front - 1.0), Vec3::Z, 0.0),
+            false,
+            None,
+        )?;
+        let from_film = self.trace(
+            Ray::new(Vec3::new(height, 0.0, 1.0), -Vec3::Z, 0.0),
+            true,
+            None,
+        )?;
+
+        let crossings = |ray: &Ray| {
+            // where the ray crosses the axis, and where it is back at its initial height
+            let focal = ray.origin.z - ray.origin.x / ray.direction.x * ray.direction.z;
+            let principal =
+                ray.origin.z + (height - ray.origin.x) / ray.direction.x * ray.direction.z;
+            (focal, principal)
+        };
+        let (film_focal, film_principal) = crossings(&from_scene);
+        let (_, scene_principal) = crossings(&from_film);
+        let focal_length = film_focal - film_principal;
+        if focal_length.is_nan() || focal_length <= 0.0 {
+            return None;
+        }
+        Some((focal_length, scene_principal, film_principal))
+    }
+
+    /// Moves the lens so that objects `distance` millimeters away from the film are
+    /// in focus, using the thick lens approximation
+    fn focus(&mut self, distance: f64) -> Option<()> {
+        let (focal_length, scene_principal, film_principal) = self.thick_lens()?;
+        // with the distances measured from the film towards the scene, and the lens
+        // moved by `shift`, the object is at `distance + scene_principal - shift`
+        // from the front principal plane, and the image at `shift - film_principal`
+        // from the rear one: the thin lens equation gives a quadratic in the latter
+        let sum = distance + scene_principal - film_principal;
+        let delta = sum * (sum - 4.0 * focal_length);
+        if delta < 0.0 {
+            return None;
+        }
+        let image_distance = 0.5 * (sum - delta.sqrt());
+        let shift = image_distance + film_principal;
+        self.elements.last_mut()?.thickness += shift;
+        self.update_positions();
+        Some(())
+    }
+}
+
+impl Projection for LensSystem {
+    fn get_ray(&self, u: f64, v: f64, r: f64, theta: f64) -> Option<Ray> {
+        self.get_filtered_ray(u, v, r, theta).map(|(ray, _)| ray)
+    }
+
+    fn get_filtered_ray(&self, u: f64, v: f64, r: f64, theta: f64) -> Option<(Ray, Colour)> {
+        // the lens flips the image
+        let x = -(u - 0.5) * self.film_size.0;
+        let y = -(v - 0.5) * self.film_size.1;
+
+        // with dispersion, each ray samples a single colour channel,
+        // picked with `theta` whose remainder samples the lens
+        let (channel, theta) = if self.abbe_number > 0.0 {
+            let k = theta / TAU * 3.0;
+            let channel = (k.floor() as usize).min(2);
+            (Some(channel), (k - channel as f64) * TAU)
+        } else {
+            (None, theta)
+        };
+
+        let (ray, weight) = self.trace_from_film(x, y, r, theta, channel)?;
+        let weight = weight * self.exposure;
+        let filter = match channel {
+            Some(0) => Colour::new(3.0 * weight, 0.0, 0.0),
+            Some(1) => Colour::new(0.0, 3.0 * weight, 0.0),
+            Some(_) => Colour::new(0.0, 0.0, 3.0 * weight),
+            None => Colour::new(weight, weight, weight),
+        };
+        Some((Ray::new(ray.origin * 1e-3, ray.direction, 0.0), filter))
+    }
+}
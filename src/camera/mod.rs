@@ -1,25 +1,40 @@
+pub mod aperture;
+pub mod cylindrical;
+pub mod equirectangular;
+pub mod fisheye;
+pub mod lens_system;
+pub mod orthographic;
+pub mod stereo;
+pub mod thin_lens;
+
+use std::fmt::Debug;
+
 use crate::ray::Ray;
-use crate::vec3::{Point3, Vec3};
+use crate::vec3::{color::Colour, Point3, Vec3};
+
+/// Maps points of the image to rays in camera space, where the camera
+/// looks towards -z, with x pointing right and y pointing up.
+pub trait Projection: Debug + Send + Sync {
+    /// Casts a ray through the point (u, v) of the image, both in [0, 1].
+    /// `r` and `theta` sample the lens. Returns `None` for the points
+    /// of the image that are outside of the field of view.
+    fn get_ray(&self, u: f64, v: f64, r: f64, theta: f64) -> Option<Ray>;
 
-#[derive(Clone, Debug)]
-pub enum CameraType {
-    ThinLens {
-        lens_radius: f64,
-        base: (Vec3, Vec3),
-        to_lower_left_corner: Vec3,
-    },
-    Isomorphic {
-        direction: Vec3,
-    },
+    /// Like `get_ray`, along with a filter applied to the light carried by the ray,
+    /// for projections that vignette or split the colour channels
+    fn get_filtered_ray(&self, u: f64, v: f64, r: f64, theta: f64) -> Option<(Ray, Colour)> {
+        self.get_ray(u, v, r, theta).map(|ray| (ray, Colour::WHITE))
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub struct Camera {
     pub origin: Point3,
-    pub lower_left_corner: Point3,
-    pub horizontal: Vec3,
-    pub vertical: Vec3,
-    pub camera_type: CameraType,
+    /// The camera space axes, expressed in world space
+    pub base: (Vec3, Vec3, Vec3),
+    pub projection: Box<dyn Projection>,
+    /// The times at which the shutter opens and closes
+    pub shutter: (f64, f64),
 }
 
 impl Camera {
@@ -27,84 +42,45 @@ impl Camera {
         origin: Point3,
         look_at: Point3,
         up_vector: Vec3,
-        aspect_ratio: f64,
-        aperture: f64,
-        focus_dist: f64,
-        vertical_fov: f64,
+        projection: Box<dyn Projection>,
     ) -> Self {
-        let theta = vertical_fov.to_radians();
-        let h = (0.5 * theta).tan();
-        let viewport_height = 2.0 * h;
-        let viewport_width = viewport_height * aspect_ratio;
-
         let w = (origin - look_at).unit_vector();
         let u = up_vector.cross(w).unit_vector();
         let v = w.cross(u);
 
-        let horizontal = u * viewport_width * focus_dist;
-        let vertical = v * viewport_height * focus_dist;
-        let lower_left_corner = origin - horizontal * 0.5 - vertical * 0.5 - w * focus_dist;
-
         Self {
             origin,
-            lower_left_corner,
-            horizontal,
-            vertical,
-            camera_type: CameraType::ThinLens {
-                lens_radius: aperture / 2.0,
-                base: (u, v),
-                to_lower_left_corner: lower_left_corner - origin,
-            },
+            base: (u, v, w),
+            projection,
+            shutter: (0.0, 0.0),
         }
     }
 
-    pub fn isomorphic(
-        origin: Point3,
-        look_at: Point3,
-        up_vector: Vec3,
-        aspect_ratio: f64,
-        vertical_fov: f64,
-    ) -> Self {
-        let theta = vertical_fov.to_radians();
-        let h = (0.5 * theta).tan();
-        let viewport_height = 2.0 * h;
-        let viewport_width = viewport_height * aspect_ratio;
-
-        let w = (origin - look_at).unit_vector();
-        let u = up_vector.cross(w).unit_vector();
-        let v = w.cross(u);
-
-        let horizontal = u * viewport_width;
-        let vertical = v * viewport_height;
-        let lower_left_corner = origin - horizontal * 0.5 - vertical * 0.5 - w;
+    pub fn with_shutter(mut self, shutter: (f64, f64)) -> Self {
+        self.shutter = shutter;
+        self
+    }
 
-        Self {
-            origin,
-            lower_left_corner,
-            horizontal,
-            vertical,
-            camera_type: CameraType::Isomorphic { direction: w },
-        }
+    fn to_world(&self, v: Vec3) -> Vec3 {
+        self.base.0 * v.x + self.base.1 * v.y + self.base.2 * v.z
     }
 
-    pub fn get_ray(&self, u: f64, v: f64, r: f64, theta: f64) -> Ray {
-        match self.camera_type {
-            CameraType::Isomorphic { direction } => Ray::new(
-                self.lower_left_corner + self.horizontal * u + self.vertical * v,
-                direction,
-            ),
-            CameraType::ThinLens {
-                lens_radius,
-                base,
-                to_lower_left_corner,
-            } => {
-                let rd = Vec3::random_in_unit_disk(r, theta) * lens_radius;
-                let offset = base.0 * rd.x + base.1 * rd.y;
-                Ray::new(
-                    self.origin + offset,
-                    to_lower_left_corner + self.horizontal * u + self.vertical * v - offset,
+    /// Casts a ray through the point (u, v) of the image. `r` and `theta` sample
+    /// the lens, and `time` in [0, 1] samples the shutter interval. The colour
+    /// filters the light carried by the ray.
+    pub fn get_ray(&self, u: f64, v: f64, r: f64, theta: f64, time: f64) -> Option<(Ray, Colour)> {
+        let time = self.shutter.0 + time * (self.shutter.1 - self.shutter.0);
+        self.projection
+            .get_filtered_ray(u, v, r, theta)
+            .map(|(ray, filter)| {
+                (
+                    Ray::new(
+                        self.origin + self.to_world(ray.origin),
+                        self.to_world(ray.direction),
+                        time,
+                    ),
+                    filter,
                 )
-            }
-        }
+            })
     }
 }
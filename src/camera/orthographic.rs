@@ -0,0 +1,29 @@
+use super::Projection;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+/// A parallel projection, showing a region `view_height` units high
+#[derive(Clone, Debug)]
+pub struct Orthographic {
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Orthographic {
+    pub fn new(aspect_ratio: f64, view_height: f64) -> Self {
+        Self {
+            width: view_height * aspect_ratio,
+            height: view_height,
+        }
+    }
+}
+
+impl Projection for Orthographic {
+    fn get_ray(&self, u: f64, v: f64, _: f64, _: f64) -> Option<Ray> {
+        Some(Ray::new(
+            Vec3::new((u - 0.5) * self.width, (v - 0.5) * self.height, 0.0),
+            -Vec3::Z,
+            0.0,
+        ))
+    }
+}
@@ -0,0 +1,102 @@
+use super::Projection;
+use crate::ray::Ray;
+use crate::vec3::{color::Colour, Vec3};
+
+/// How the views of the two eyes are packed into the image
+#[derive(Clone, Copy, Debug)]
+pub enum StereoLayout {
+    /// The left eye in the left half of the image
+    SideBySide,
+    /// The left eye in the top half of the image
+    TopBottom,
+}
+
+/// Renders the views of two eyes, separated by `interocular_distance` along the
+/// camera x axis, into a single image.
+///
+/// The eyes converge at depth `convergence`, where the two views line up; a null
+/// convergence keeps the eyes parallel. In `omnidirectional` mode, the eyes turn
+/// around the vertical axis with the view direction, which gives an
+/// omnidirectional stereo panorama when used with a panoramic projection.
+#[derive(Debug)]
+pub struct Stereo {
+    pub eye: Box<dyn Projection>,
+    pub interocular_distance: f64,
+    pub convergence: f64,
+    pub layout: StereoLayout,
+    pub omnidirectional: bool,
+}
+
+impl Stereo {
+    /// `eye` is the projection of a single eye, with the aspect ratio of its half
+    /// of the image
+    pub fn new(
+        eye: Box<dyn Projection>,
+        interocular_distance: f64,
+        convergence: f64,
+        layout: StereoLayout,
+        omnidirectional: bool,
+    ) -> Self {
+        Self {
+            eye,
+            interocular_distance,
+            convergence,
+            layout,
+            omnidirectional,
+        }
+    }
+}
+
+impl Projection for Stereo {
+    fn get_ray(&self, u: f64, v: f64, r: f64, theta: f64) -> Option<Ray> {
+        self.get_filtered_ray(u, v, r, theta).map(|(ray, _)| ray)
+    }
+
+    fn get_filtered_ray(&self, u: f64, v: f64, r: f64, theta: f64) -> Option<(Ray, Colour)> {
+        // the sign is -1 for the left eye and 1 for the right one
+        let (sign, u, v) = match self.layout {
+            StereoLayout::SideBySide if u < 0.5 => (-1.0, 2.0 * u, v),
+            StereoLayout::SideBySide => (1.0, 2.0 * u - 1.0, v),
+            StereoLayout::TopBottom if v >= 0.5 => (-1.0, u, 2.0 * v - 1.0),
+            StereoLayout::TopBottom => (1.0, u, 2.0 * v),
+        };
+        let (ray, filter) = self.eye.get_filtered_ray(u, v, r, theta)?;
+        let half = 0.5 * sign * self.interocular_distance;
+
+        if self.omnidirectional {
+            // the eye lies on a horizontal circle, perpendicular to the view direction
+            let right = Vec3::new(-ray.direction.z, 0.0, ray.direction.x);
+            let length = right.length();
+            if length < 1e-12_f64 {
+                return Some((ray, filter));
+            }
+            let offset = right * (half / length);
+            let direction = if self.convergence > 0.0 {
+                ray.direction.unit_vector() - offset / self.convergence
+            } else {
+                ray.direction
+            };
+            return Some((Ray::new(ray.origin + offset, direction, ray.time), filter));
+        }
+
+        // Off-axis stereo: the camera space is sheared so that the eye moves by
+        // the offset while the plane at depth `convergence` stays in place.
+        // The shear is affine, so it keeps the focus of a thin lens.
+        let offset = Vec3::X * half;
+        if self.convergence > 0.0 {
+            Some((
+                Ray::new(
+                    ray.origin + offset * (1.0 + ray.origin.z / self.convergence),
+                    ray.direction + offset * (ray.direction.z / self.convergence),
+                    ray.time,
+                ),
+                filter,
+            ))
+        } else {
+            Some((
+                Ray::new(ray.origin + offset, ray.direction, ray.time),
+                filter,
+            ))
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use super::aperture::Aperture;
+use super::Projection;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+
+/// A perspective projection through a thin lens, focused at `focus_dist`
+#[derive(Clone, Debug)]
+pub struct ThinLens {
+    pub lens_radius: f64,
+    pub lower_left_corner: Point3,
+    pub horizontal: Vec3,
+    pub vertical: Vec3,
+    pub aperture: Aperture,
+    /// The shift of the lens barrel relative to the aperture in the corners of
+    /// the image, as a fraction of the lens radius. Off-axis out of focus
+    /// highlights get clipped into a cat's eye shape, and the corners darken.
+    pub cat_eye: f64,
+    pub focus_dist: f64,
+    /// The normal of the plane in focus, along the view direction unless the lens is tilted
+    pub focus_normal: Vec3,
+}
+
+impl ThinLens {
+    pub fn new(aspect_ratio: f64, aperture: f64, focus_dist: f64, vertical_fov: f64) -> Self {
+        let theta = vertical_fov.to_radians();
+        let h = (0.5 * theta).tan();
+        let viewport_height = 2.0 * h;
+        let viewport_width = viewport_height * aspect_ratio;
+
+        let horizontal = Vec3::X * viewport_width * focus_dist;
+        let vertical = Vec3::Y * viewport_height * focus_dist;
+        let lower_left_corner = -horizontal * 0.5 - vertical * 0.5 - Vec3::Z * focus_dist;
+
+        Self {
+            lens_radius: aperture / 2.0,
+            lower_left_corner,
+            horizontal,
+            vertical,
+            aperture: Aperture::Circle,
+            cat_eye: 0.0,
+            focus_dist,
+            focus_normal: Vec3::Z,
+        }
+    }
+
+    /// Creates a lens from the parameters of a real camera: the focal length and
+    /// the height of the sensor in millimeters, and the f-number. The scene is
+    /// assumed to be measured in meters.
+    pub fn physical(
+        aspect_ratio: f64,
+        focal_length: f64,
+        f_stop: f64,
+        sensor_height: f64,
+        focus_dist: f64,
+    ) -> Self {
+        let vertical_fov = 2.0 * (0.5 * sensor_height / focal_length).atan().to_degrees();
+        let aperture = focal_length / f_stop * 1e-3;
+        Self::new(aspect_ratio, aperture, focus_dist, vertical_fov)
+    }
+
+    pub fn with_aperture(mut self, aperture: Aperture) -> Self {
+        self.aperture = aperture;
+        self
+    }
+
+    pub fn with_cat_eye(mut self, cat_eye: f64) -> Self {
+        self.cat_eye = cat_eye;
+        self
+    }
+
+    /// Shifts the lens parallel to the film, by fractions of the width and the
+    /// height of the image. Unlike rotating the camera, this keeps lines parallel
+    /// to the film straight, such as the verticals of a building.
+    pub fn with_shift(mut self, shift: (f64, f64)) -> Self {
+        self.lower_left_corner =
+            self.lower_left_corner + self.horizontal * shift.0 + self.vertical * shift.1;
+        self
+    }
+
+    /// Tilts the plane in focus by angles in degrees around the horizontal and the
+    /// vertical axes of the image, following the Scheimpflug principle. A positive
+    /// vertical tilt brings the plane in focus closer at the bottom of the image,
+    /// and a positive horizontal tilt closer on the left.
+    pub fn with_tilt(mut self, tilt: (f64, f64)) -> Self {
+        let (sin_a, cos_a) = tilt.0.to_radians().sin_cos();
+        let (sin_b, cos_b) = tilt.1.to_radians().sin_cos();
+        self.focus_normal = Vec3::new(sin_b * cos_a, sin_a, cos_b * cos_a).unit_vector();
+        self
+    }
+}
+
+impl Projection for ThinLens {
+    fn get_ray(&self, u: f64, v: f64, r: f64, theta: f64) -> Option<Ray> {
+        let target = self.lower_left_corner + self.horizontal * u + self.vertical * v;
+        let sample = self.aperture.sample(r, theta);
+        if self.cat_eye > 0.0 {
+            // the barrel shifts outwards with the distance to the optical axis
+            let (x, y) = (target.x, target.y);
+            let diagonal = 0.5 * self.horizontal.x.hypot(self.vertical.y);
+            let shift = Vec3::new(x, y, 0.0) * (self.cat_eye / diagonal);
+            if (sample - shift).length_squared() > 1.0 {
+                return None;
+            }
+        }
+        // the point in focus is where the ray through the center of the lens
+        // meets the plane in focus
+        let target =
+            target * (-self.focus_dist * self.focus_normal.z / self.focus_normal.dot(target));
+        let offset = sample * self.lens_radius;
+        Some(Ray::new(offset, target - offset, 0.0))
+    }
+}
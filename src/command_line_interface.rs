@@ -1,15 +1,16 @@
-use std::{fs::File, path::PathBuf};
+use std::{fs::File, ops::Range, path::PathBuf, time::Duration};
 
 use anyhow::{ensure, Context, Result};
 use clap::{AppSettings, Clap, ValueHint};
 use rand::Rng;
 use ron::ser::{to_writer_pretty, PrettyConfig};
 
+use crate::terminal_preview::Protocol;
 use crate::world_loader::Config;
 use crate::FastRng;
 
 /// A CPU-based raytracer
-#[derive(Clap)]
+#[derive(Clap, Clone)]
 #[clap(version = "1.0", author = "César Sagaert <sagaert.cesar@gmail.com>")]
 #[clap(setting = AppSettings::ColoredHelp)]
 #[clap(setting = AppSettings::ArgRequiredElseHelp)]
@@ -25,6 +26,58 @@ pub enum Opts {
         /// Displays the BVH tree
         #[clap(short, long)]
         tree: bool,
+        /// Saves an image of the number of samples taken in each pixel
+        #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+        spp_map: Option<PathBuf>,
+        /// Stops rendering after this many seconds, and saves the image as it is.
+        /// With `--watch`, applies to each render.
+        #[clap(long)]
+        time_limit: Option<f64>,
+        /// Overwrites the output image with the current render every this many seconds
+        #[clap(long)]
+        preview_interval: Option<f64>,
+        /// Periodically saves the state of the render to this file, and when it stops
+        #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+        checkpoint: Option<PathBuf>,
+        /// The time between two checkpoints, in seconds
+        #[clap(long, default_value = "300")]
+        checkpoint_interval: f64,
+        /// Continues a render from a checkpoint file, and then saves checkpoints
+        /// to it unless `--checkpoint` is given
+        #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+        resume: Option<PathBuf>,
+        /// Renders again whenever the config file or a file it references changes,
+        /// writing a preview of the render every second by default
+        #[clap(short, long)]
+        watch: bool,
+        /// Shows a live preview of the render in the terminal: auto, blocks or sixel
+        #[clap(long)]
+        terminal_preview: Option<Protocol>,
+        /// Renders these frames of the animation of the scene, such as `0..120` or
+        /// `1..=120`, to images numbered like `scene_0001.png`
+        #[clap(long, parse(try_from_str = frames))]
+        frames: Option<Range<u32>>,
+        #[clap(flatten)]
+        overrides: Overrides,
+    },
+    /// Renders several scenes, or variants of scenes sweeping over config values
+    Batch {
+        /// .ron configuration files
+        #[clap(required = true, parse(from_os_str), value_hint = ValueHint::FilePath)]
+        configs: Vec<PathBuf>,
+        /// The output image files, where `{name}` is replaced by the name of the
+        /// config file, `{index}` by the number of the variant, and `{PATH}` by the
+        /// value of the swept PATH. `{name}.png` by default, `{name}_{index}.png` with sweeps.
+        #[clap(short, long)]
+        output: Option<String>,
+        /// Renders a variant for each value of a config value, either
+        /// `PATH=FROM..TO:STEPS` for STEPS values evenly spaced from FROM to TO,
+        /// or `PATH=[VALUE, ...]` with values written as in the config file.
+        /// Several sweeps render every combination of their values.
+        #[clap(long = "sweep", value_name = "PATH=VALUES", number_of_values = 1)]
+        sweeps: Vec<String>,
+        #[clap(flatten)]
+        overrides: Overrides,
     },
     /// Renders a semi-randomly generated scene
     Random {
@@ -41,10 +94,95 @@ pub enum Opts {
         #[clap(short, long)]
         tree: bool,
     },
+    /// Coordinates the render of a scene by worker processes connecting over TCP
+    Serve {
+        /// A .ron configuration file. The files it references must be at the same
+        /// path on the machines of the workers.
+        #[clap(parse(from_os_str), value_hint = ValueHint::FilePath)]
+        config: PathBuf,
+        /// An output image file. Can be a .jpeg or .png
+        #[clap(short, long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+        output: Option<PathBuf>,
+        /// The address to listen on for workers
+        #[clap(long, default_value = "0.0.0.0:7878")]
+        bind: String,
+        /// The number of samples per pixel of a tile a worker renders at once
+        #[clap(long, default_value = "16")]
+        samples_per_task: u32,
+    },
+    /// Renders tasks for a coordinator started with `serve`
+    Worker {
+        /// The address of the coordinator, such as localhost:7878
+        address: String,
+    },
+    /// Runs an HTTP API rendering the scenes posted to it, one at a time
+    #[clap(name = "server")]
+    Http {
+        /// The address to listen on
+        #[clap(long, default_value = "127.0.0.1:8080")]
+        bind: String,
+        /// The directory relative paths in the scenes are resolved from
+        #[clap(long, default_value = ".", parse(from_os_str), value_hint = ValueHint::DirPath)]
+        directory: PathBuf,
+    },
     /// Display an example configuration file
     Example,
 }
 
+/// Values of the config file to override
+#[derive(Clap, Clone, Debug)]
+pub struct Overrides {
+    /// Overrides the number of samples per pixel of the config file
+    #[clap(long)]
+    pub spp: Option<u32>,
+    /// Overrides the image height of the config file
+    #[clap(long)]
+    pub height: Option<u32>,
+    /// Overrides the maximum bounce depth of the config file
+    #[clap(long)]
+    pub max_depth: Option<u32>,
+    /// Overrides any value of the config file, written as in the file, such as
+    /// `camera.aperture=0.2` or `image.sampler=Halton`. Can be repeated.
+    #[clap(long, value_name = "PATH=VALUE", number_of_values = 1)]
+    pub set: Vec<String>,
+}
+
+impl Overrides {
+    pub fn apply(&self, config: &mut Config) -> Result<()> {
+        let image = &mut config.image;
+        image.samples_per_pixel = self.spp.unwrap_or(image.samples_per_pixel);
+        image.height = self.height.unwrap_or(image.height);
+        image.max_depth = self.max_depth.unwrap_or(image.max_depth);
+        for assignment in &self.set {
+            let (path, value) = assignment
+                .split_once('=')
+                .with_context(|| format!("Expecting PATH=VALUE, got `{}`", assignment))?;
+            config
+                .set(path.trim(), value)
+                .with_context(|| format!("Error overriding `{}`", path.trim()))?;
+        }
+        Ok(())
+    }
+}
+
+/// How often a render is saved in watch mode, unless `--preview-interval` is given
+const WATCH_PREVIEW_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How to render a scene, besides its configuration
+#[derive(Clone, Debug)]
+pub struct RenderOptions {
+    pub output: PathBuf,
+    pub tree: bool,
+    pub spp_map: Option<PathBuf>,
+    pub time_limit: Option<Duration>,
+    pub preview_interval: Option<Duration>,
+    pub checkpoint: Option<PathBuf>,
+    pub checkpoint_interval: Duration,
+    pub resume: Option<PathBuf>,
+    pub terminal_preview: Option<Protocol>,
+    pub frames: Option<Range<u32>>,
+}
+
 #[derive(Clone, Debug)]
 pub struct EarlyReturn;
 
@@ -55,21 +193,74 @@ impl std::fmt::Display for EarlyReturn {
 }
 
 impl Opts {
-    pub fn parse(self, rng: &mut impl Rng) -> Result<(Config, PathBuf, bool)> {
+    /// Checks the options of a render that can not be used together, before
+    /// reading the config file
+    pub fn check(&self) -> Result<()> {
+        if let Self::Render {
+            config,
+            checkpoint,
+            resume,
+            watch,
+            frames,
+            ..
+        } = self
+        {
+            ensure!(
+                config.extension().map(|s| s == "ron").unwrap_or_default(),
+                "Expecting a .ron config file."
+            );
+            ensure!(
+                frames.is_none() || (checkpoint.is_none() && resume.is_none()),
+                "Checkpoints can not be used when rendering frames."
+            );
+            ensure!(
+                !watch || (checkpoint.is_none() && resume.is_none()),
+                "Checkpoints can not be used when watching the config file."
+            );
+        }
+        Ok(())
+    }
+
+    pub fn parse(self, rng: &mut impl Rng) -> Result<(Config, RenderOptions)> {
+        self.check()?;
         match self {
             Self::Render {
                 config,
                 output,
                 tree,
+                spp_map,
+                time_limit,
+                preview_interval,
+                checkpoint,
+                checkpoint_interval,
+                resume,
+                watch,
+                terminal_preview,
+                frames,
+                overrides,
             } => {
-                ensure!(
-                    config.extension().map(|s| s == "ron").unwrap_or_default(),
-                    "Expecting a .ron config file."
-                );
-                let parsed_config =
+                let mut parsed_config =
                     Config::parse(&config).with_context(|| "Error parsing the config file")?;
+                overrides.apply(&mut parsed_config)?;
                 let output_file = output.unwrap_or_else(|| config.with_extension("png"));
-                Ok((parsed_config, output_file, tree))
+                Ok((
+                    parsed_config,
+                    RenderOptions {
+                        output: output_file,
+                        tree,
+                        spp_map,
+                        time_limit: time_limit.map(seconds).transpose()?,
+                        preview_interval: match preview_interval {
+                            None if watch => Some(WATCH_PREVIEW_INTERVAL),
+                            interval => interval.map(seconds).transpose()?,
+                        },
+                        checkpoint: checkpoint.or_else(|| resume.clone()),
+                        checkpoint_interval: seconds(checkpoint_interval)?,
+                        resume,
+                        terminal_preview,
+                        frames,
+                    },
+                ))
             }
             Self::Random {
                 output,
@@ -91,31 +282,120 @@ impl Opts {
                 println!(
                     "Rendering a random scene (image size: {}x{}, {}spp).",
                     config.image.height,
-                    config.image.height * config.aspect_ratio(),
+                    config.image.height * &config.aspect_ratio(),
                     config.image.samples_per_pixel
                 );
-                Ok((config, output, tree))
+                Ok((
+                    config,
+                    RenderOptions {
+                        output,
+                        tree,
+                        spp_map: None,
+                        time_limit: None,
+                        preview_interval: None,
+                        checkpoint: None,
+                        checkpoint_interval: Duration::from_secs(300),
+                        resume: None,
+                        terminal_preview: None,
+                        frames: None,
+                    },
+                ))
             }
-            Self::Example => {
+            Self::Example
+            | Self::Batch { .. }
+            | Self::Serve { .. }
+            | Self::Worker { .. }
+            | Self::Http { .. } => {
                 panic!("This case should have been handled earlier.")
             }
         }
     }
 }
 
-const EXAMPLE_FILE: &str = r#"/*
+/// Parses `FROM..TO`, `FROM..=TO` or a single frame
+fn frames(text: &str) -> Result<Range<u32>, String> {
+    let number = |s: &str| {
+        s.trim()
+            .parse::<u32>()
+            .map_err(|_| format!("invalid frame number `{}`", s.trim()))
+    };
+    // the end of the range is past the last frame
+    let after = |frame: u32| {
+        frame
+            .checked_add(1)
+            .ok_or_else(|| format!("frame {} is too large", frame))
+    };
+    let frames = if let Some((from, to)) = text.split_once("..=") {
+        number(from)?..after(number(to)?)?
+    } else if let Some((from, to)) = text.split_once("..") {
+        number(from)?..number(to)?
+    } else {
+        let frame = number(text)?;
+        frame..after(frame)?
+    };
+    if frames.is_empty() {
+        return Err(format!("no frames in `{}`", text));
+    }
+    Ok(frames)
+}
+
+fn seconds(value: f64) -> Result<Duration> {
+    ensure!(
+        value.is_finite() && value > 0.0,
+        "Expecting a positive number of seconds, got {}.",
+        value
+    );
+    Ok(Duration::from_secs_f64(value))
+}
+
+pub(crate) const EXAMPLE_FILE: &str = r#"/*
 === SAMPLE CONFIG FILE === (does not render anything pretty)
 Available constant colors: Red, Yellow, Green, Cyan, Blue, Magenta, Black, White
 Other color formats: Rgb(float, float, float), Hex(int)
 */
 Config(
+    // include: ["materials.ron", "cameras/wide.ron"], // optional, config files with any of
+    //     the values of this one, relative to it. Later files override earlier ones, and this
+    //     one overrides them: materials are merged by name, objects are added, and other
+    //     values replaced. Relative paths in included files are relative to them.
     image: (
         height: 400, // image height in pixels
         samples_per_pixel: 200, // the number of rays cast per pixel
         max_depth: 50, // the maximum bounce depth
+        filter: Mitchell(radius: 2, b: 0.33, c: 0.33), // optional, Box(radius: 0.5) by default,
+                // or Tent(radius: 1), Gaussian(radius: 1.5, alpha: 2), Lanczos(radius: 3)
+        sampler: Sobol, // optional, or Independent, Stratified, Halton, BlueNoise
+        adaptive_threshold: 0.02, // optional, stops sampling a pixel once the relative
+                                  // error of its mean is below, samples_per_pixel being the maximum
+        min_samples_per_pixel: 16, // optional, with adaptive sampling
+        tile_size: 16, // optional, the image is rendered by square tiles of this side
+        tile_order: CentreOut, // optional, or Hilbert
     ),
-    camera: ThinLens( // the camera can also be isomorphic,
-                      // in which case aperture and focus distance are not needed.
+    // Other cameras share origin, look_at, up_vector, aspect_ratio and shutter:
+    //   Orthographic(view_height: 4, ...), the height of the view in world units
+    //   Fisheye(fov: 180, ...), equidistant, the image circle spans the image height
+    //   Equirectangular(...), a 360° panorama, best with a 2:1 aspect ratio
+    //   Cylindrical(horizontal_fov: 360, ...), horizontal_fov is optional
+    //   PhysicalLens(focal_length: 50, f_stop: 2.8, ...), replaces aperture and vertical_fov,
+    //       with an optional sensor_height in mm (24 by default)
+    //   LensSystem(
+    //       lens: DoubleGauss50mm, // or WideAngle22mm, Fisheye10mm, Telephoto250mm,
+    //           // or Custom([(radius, thickness, ior, aperture), ...]) in mm, front to film
+    //       sensor_height: 24, // optional, in mm
+    //       focus_distance: 10, // optional, the lens is focused with a thick lens model
+    //       stop_diameter: 8, // optional, in mm, defaults to the one of the lens
+    //       abbe_number: 50, // optional, enables chromatic aberration
+    //       ...
+    //   )
+    // Any camera can be rendered in stereo, with the aspect ratio of a single eye:
+    //   Stereo(
+    //       camera: Equirectangular(...),
+    //       interocular_distance: 0.064,
+    //       convergence: 10, // optional, the eyes are parallel by default
+    //       layout: TopBottom, // optional, SideBySide by default
+    //       omnidirectional: true, // optional, for VR panoramas
+    //   )
+    camera: ThinLens(
         origin: (13, 2, 3),
         look_at: (0, 0, 0),
         up_vector: (0, 1, 0), // decides of the camera orientation
@@ -123,6 +403,13 @@ Config(
         aperture: 0.1,
         vertical_fov: 30,
         focus_distance: 10, // optional
+        aperture_shape: Polygon(blades: 6, rotation: 15), // optional, Circle by default,
+                        // or Mask(image: "bokeh.png") with a path relative to this file
+        cat_eye: 0.3, // optional, clips out of focus highlights near the image corners
+        shift: (0, 0.1), // optional, lens shift as fractions of the image width and height
+        tilt: (5, 0), // optional, tilts the plane in focus around the horizontal
+                      // and vertical axes, in degrees
+        shutter: (0, 1), // optional, the shutter interval for motion blur
     ),
     world: (
         background_color: Rgb(0.2, 0.2, 0.2),
@@ -174,9 +461,108 @@ Config(
                 radius: 1,
                 density: 0.9,
                 material: "diffuse",
+            ),
+            Cylinder(
+                base: (3, 0, -2),
+                top: (3, 2, -2),
+                radius: 0.5,
+                open: false, // optional, removes the end caps
+                material: "plastic",
+            ),
+            Cone(
+                base: (5, 0, -2),
+                apex: (5, 1.5, -2),
+                radius: 0.5,
+                material: "metal",
+            ),
+            Disk(
+                center: (0, -1, 0),
+                normal: (0, 1, 0),
+                radius: 10,
+                material: "diffuse",
+            ),
+            Plane( // infinite, kept outside of the BVH
+                point: (0, -2, 0),
+                normal: (0, 1, 0),
+                material: "diffuse",
+            ),
+            Difference( // also available: Union and Intersection
+                left: Sphere(
+                    center: (-6, 1, 0),
+                    radius: 1,
+                    material: "glass",
+                ),
+                right: Cylinder( // the right object is carved out of the left one
+                    base: (-6, 0, 0),
+                    top: (-6, 2, 0),
+                    radius: 0.5,
+                    material: "glass",
+                ),
+            ),
+            Sdf( // a signed distance field, rendered by sphere tracing
+                shape: Union(
+                    left: RoundBox(center: (0, 1, 0), half_extents: (1, 0.5, 1), radius: 0.1),
+                    right: Twist(
+                        amount: 1.5, // twists around the y axis
+                        shape: Box(center: (0, 2, 0), half_extents: (0.3, 1, 0.3)),
+                    ),
+                    smoothness: 0.3, // optional
+                ),
+                // Other shapes: Sphere, Torus, Capsule(a, b, radius),
+                //               Mandelbulb(center, scale, power, iterations)
+                // Other operators: Subtraction, Repetition(period, shape)
+                bounds: ((-1.5, 0, -1.5), (1.5, 3.5, 1.5)), // clips the shape
+                material: "plastic",
+            ),
+            /* Heightfield( // a terrain from a 8 or 16-bit grayscale image
+                image: "terrain.png", // relative to the config file
+                origin: (-50, -2, -50), // optional
+                size: (100, 100), // along the x and z axes
+                height_scale: 10,
+                material: "diffuse",
+            ), */
+            MovingSphere( // moves from start at time 0 to end at time 1
+                start: (4, 1, 4),
+                end: (4, 1.5, 4),
+                radius: 0.5,
+                material: "diffuse",
+            ),
+            Instance( // an object moved by a keyframed transform
+                object: Quad(
+                    vertex: (-0.5, 0, -0.5),
+                    edges: ((1, 0, 0), (0, 0, 1)),
+                    material: "metal",
+                ),
+                keyframes: [ // translation, rotation (in degrees) and scale are optional
+                    (time: 0, translation: (0, 3, 0)),
+                    (time: 1, translation: (0, 3.5, 0), rotation: (0, 45, 0), scale: 1.5),
+                ],
+            ),
+            Torus(
+                center: (-3, 1, 2),
+                axis: (0, 1, 0),
+                major_radius: 1,
+                minor_radius: 0.25,
+                material: "metal",
             )
         ]
-    )
+    ),
+    // optional, values changing from frame to frame with `render --frames 0..120`
+    animation: Some((
+        tracks: { // by their path in the config, as with `render --set`
+            "camera.origin": (
+                interpolation: CatmullRom, // optional, Linear by default, or Bezier
+                keys: [(frame: 0, value: (13, 2, 3)), (frame: 60, value: (3, 2, 13)),
+                    (frame: 119, value: (-13, 2, 3))],
+            ),
+            "world.materials.metal.fuzziness": (
+                keys: [(frame: 0, value: 0), (frame: 119, value: 0.5)],
+            ),
+            "world.objects.11.keyframes.0.rotation": ( // the transform of the instance
+                keys: [(frame: 0, value: (0, 0, 0)), (frame: 120, value: (0, 360, 0))],
+            ),
+        },
+    )),
 )"#;
 
 pub fn example_config_file() {
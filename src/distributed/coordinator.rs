@@ -0,0 +1,223 @@
+use std::collections::VecDeque;
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Mutex,
+};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{ensure, Context, Result};
+use indicatif::{ProgressBar, ProgressFinish, ProgressStyle};
+
+use super::{receive, send, Request, TaskResult};
+use crate::film::{filter::Filter, Film};
+use crate::renderer::{
+    tile::{self, Tile},
+    CancellationToken,
+};
+use crate::world_loader::Config;
+
+/// How long to wait before checking again for new workers or tasks
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long a worker has to return a task, besides the time per sample, before
+/// the task is given to another worker
+const TASK_TIMEOUT: Duration = Duration::from_secs(60);
+const TASK_TIMEOUT_PER_SAMPLE: Duration = Duration::from_secs(10);
+
+/// The state shared by the threads talking to the workers
+struct Coordinator {
+    config: String,
+    directory: PathBuf,
+    filter: Filter,
+    film: Film,
+    /// How long to wait for a worker to answer
+    timeout: Duration,
+    tasks: Mutex<VecDeque<(Tile, Range<u32>)>>,
+    total: usize,
+    completed: AtomicUsize,
+    cancellation: CancellationToken,
+    pb: ProgressBar,
+}
+
+impl Coordinator {
+    fn is_done(&self) -> bool {
+        self.completed.load(Ordering::Relaxed) == self.total || self.cancellation.is_cancelled()
+    }
+
+    /// Sends tasks to a worker until there are none left. The task being
+    /// rendered when the worker fails goes back into the queue.
+    fn serve_worker(&self, mut stream: TcpStream) -> Result<()> {
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        send(
+            &mut stream,
+            &Request::Scene {
+                config: self.config.clone(),
+                directory: self.directory.clone(),
+            },
+        )?;
+        while !self.is_done() {
+            // tasks can come back into the queue until they are all completed
+            let task = match self.tasks.lock().unwrap().pop_front() {
+                Some(task) => task,
+                None => {
+                    thread::sleep(POLL_INTERVAL);
+                    continue;
+                }
+            };
+            let film = send(
+                &mut stream,
+                &Request::Task {
+                    tile: task.0,
+                    samples: task.1.clone(),
+                },
+            )
+            .and_then(|_| receive::<TaskResult>(&mut stream))
+            .and_then(|result| self.check_result(task.0, result))
+            .and_then(|result| result.to_film(self.filter.clone()));
+            match film {
+                Ok(film) => {
+                    self.film.merge(&film);
+                    self.completed.fetch_add(1, Ordering::Relaxed);
+                    self.pb.inc(1);
+                }
+                Err(error) => {
+                    self.tasks.lock().unwrap().push_front(task);
+                    return Err(error);
+                }
+            }
+        }
+        send(&mut stream, &Request::Done)
+    }
+
+    /// Checks that the film of a task covers the tile and its margin, as the
+    /// film of the image would drop anything outside of it
+    fn check_result(&self, tile: Tile, result: TaskResult) -> Result<TaskResult> {
+        let window = tile.with_margin(
+            self.filter.radius().ceil() as u32,
+            self.film.width,
+            self.film.height,
+        );
+        ensure!(
+            result.origin == (window.x, window.y)
+                && (result.width, result.height) == (window.width, window.height),
+            "Expecting the result of a task at {:?} of {}x{} pixels, got one at {:?} of {}x{} pixels.",
+            (window.x, window.y),
+            window.width,
+            window.height,
+            result.origin,
+            result.width,
+            result.height
+        );
+        Ok(result)
+    }
+}
+
+/// Renders the scene of a config file with workers connecting to `address`,
+/// which each take tasks of `samples_per_task` samples in a tile. Adaptive
+/// sampling is not supported: every pixel gets `samples_per_pixel` samples.
+pub fn serve(
+    config_file: &Path,
+    output: &Path,
+    address: &str,
+    samples_per_task: u32,
+) -> Result<()> {
+    let text = std::fs::read_to_string(config_file)
+        .with_context(|| format!("Could not read `{}`", config_file.display()))?;
+    // workers resolve relative paths from the same directory, on their machine
+    let directory = config_file
+        .canonicalize()?
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let config = Config::parse_str(&text, directory.clone())
+        .with_context(|| "Error parsing the config file")?;
+
+    let height = config.image.height;
+    let width = height * &config.aspect_ratio();
+    let samples_per_pixel = config.image.samples_per_pixel;
+    let tiles = tile::tiles(
+        width,
+        height,
+        config.image.tile_size,
+        config.image.tile_order.into(),
+    );
+    let tasks: VecDeque<_> = (0..samples_per_pixel)
+        .step_by(samples_per_task.max(1) as usize)
+        .flat_map(|start| {
+            let end = (start + samples_per_task.max(1)).min(samples_per_pixel);
+            tiles.iter().map(move |&tile| (tile, start..end))
+        })
+        .collect();
+
+    let filter: Filter = (&config.image.filter).into();
+    let cancellation = CancellationToken::default();
+    cancellation.cancel_on_ctrlc()?;
+    let pb = ProgressBar::new(tasks.len() as u64).with_style(
+        ProgressStyle::default_bar()
+            .template("[{elapsed_precise}] {bar:50} {pos}/{len} tasks")
+            .on_finish(ProgressFinish::AndLeave),
+    );
+    let coordinator = Coordinator {
+        config: text,
+        directory,
+        film: Film::new(width, height, filter.clone()),
+        filter,
+        timeout: TASK_TIMEOUT + TASK_TIMEOUT_PER_SAMPLE * samples_per_task.max(1),
+        total: tasks.len(),
+        tasks: Mutex::new(tasks),
+        completed: AtomicUsize::new(0),
+        cancellation,
+        pb,
+    };
+
+    let listener =
+        TcpListener::bind(address).with_context(|| format!("Could not listen on {}", address))?;
+    listener.set_nonblocking(true)?;
+    println!(
+        "Rendering a {}x{} image in {} tasks, waiting for workers on {}",
+        width, height, coordinator.total, address
+    );
+
+    thread::scope(|scope| -> Result<()> {
+        while !coordinator.is_done() {
+            match listener.accept() {
+                Ok((stream, peer)) => {
+                    stream.set_nonblocking(false)?;
+                    coordinator.pb.println(format!("Worker {} connected", peer));
+                    let coordinator = &coordinator;
+                    scope.spawn(move || {
+                        if let Err(error) = coordinator.serve_worker(stream) {
+                            coordinator
+                                .pb
+                                .println(format!("Worker {} failed: {:#}", peer, error));
+                        }
+                    });
+                }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+                Err(error) => return Err(error.into()),
+            }
+        }
+        Ok(())
+    })?;
+
+    if coordinator.cancellation.is_cancelled() {
+        coordinator.pb.abandon();
+        println!("Rendering stopped early.");
+    } else {
+        coordinator.pb.finish();
+    }
+    println!(
+        "Scene rendered in {} seconds.\nSaving as {}...",
+        coordinator.pb.elapsed().as_secs(),
+        output.display()
+    );
+    coordinator.film.to_image().save(output)?;
+    println!("Successfully saved image.");
+    Ok(())
+}
@@ -0,0 +1,108 @@
+//! Rendering on several machines: a coordinator splits the image into tasks,
+//! tiles with a range of samples, which workers connected over TCP render and
+//! send back as the float accumulation buffers of their films.
+//!
+//! Messages are RON documents, prefixed by their length as a little endian u64.
+
+pub mod coordinator;
+pub mod worker;
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, ensure, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::film::{filter::Filter, Film};
+use crate::renderer::tile::Tile;
+use crate::vec3::color::Colour;
+
+/// Messages are limited to 1 GiB, to fail early on a corrupted stream
+const MAX_MESSAGE_LENGTH: u64 = 1 << 30;
+
+/// A message from the coordinator to a worker
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Request {
+    /// Sent once on connection: the text of the config file, and the directory
+    /// its relative paths are resolved from, which the worker must also have
+    Scene {
+        config: String,
+        directory: PathBuf,
+    },
+    Task {
+        tile: Tile,
+        samples: Range<u32>,
+    },
+    /// There are no more tasks, the worker can disconnect
+    Done,
+}
+
+/// The film a worker accumulated the samples of a task into
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TaskResult {
+    pub origin: (u32, u32),
+    pub width: u32,
+    pub height: u32,
+    /// The weighted sum of the samples and the total weight of each pixel
+    pub pixels: Vec<(f64, f64, f64, f64)>,
+}
+
+impl From<&Film> for TaskResult {
+    fn from(film: &Film) -> Self {
+        Self {
+            origin: film.origin,
+            width: film.width,
+            height: film.height,
+            pixels: film
+                .accumulated()
+                .into_iter()
+                .map(|(colour, weight)| (colour.r, colour.g, colour.b, weight))
+                .collect(),
+        }
+    }
+}
+
+impl TaskResult {
+    pub fn to_film(&self, filter: Filter) -> Result<Film> {
+        ensure!(
+            self.pixels.len() == self.width as usize * self.height as usize,
+            "Expecting {}x{} pixels in a task result, got {}.",
+            self.width,
+            self.height,
+            self.pixels.len()
+        );
+        let mut film = Film::new(self.width, self.height, filter).with_origin(self.origin);
+        let pixels: Vec<_> = self
+            .pixels
+            .iter()
+            .map(|&(r, g, b, weight)| (Colour::new(r, g, b), weight))
+            .collect();
+        film.restore(&pixels);
+        Ok(film)
+    }
+}
+
+pub fn send<T: Serialize>(stream: &mut TcpStream, message: &T) -> Result<()> {
+    let text = ron::ser::to_string(message)?;
+    // in a single write, so that the length and the message go in the same packet
+    let mut bytes = (text.len() as u64).to_le_bytes().to_vec();
+    bytes.extend_from_slice(text.as_bytes());
+    stream.write_all(&bytes)?;
+    Ok(())
+}
+
+pub fn receive<T: DeserializeOwned>(stream: &mut TcpStream) -> Result<T> {
+    let mut length = [0; 8];
+    stream.read_exact(&mut length)?;
+    let length = u64::from_le_bytes(length);
+    ensure!(
+        length <= MAX_MESSAGE_LENGTH,
+        "Message of {} bytes is too long.",
+        length
+    );
+    let mut text = vec![0; length as usize];
+    stream.read_exact(&mut text)?;
+    ron::de::from_bytes(&text).map_err(|e| anyhow!(e))
+}
@@ -0,0 +1,47 @@
+use std::net::TcpStream;
+
+use anyhow::{bail, Context, Result};
+
+use super::{receive, send, Request, TaskResult};
+use crate::bounding_boxes::Scene;
+use crate::film::filter::Filter;
+use crate::renderer::tracer::Tracer;
+use crate::world_loader::Config;
+use crate::FastRng;
+
+/// Connects to a coordinator, and renders the tasks it sends until it has no more
+pub fn work(address: &str) -> Result<()> {
+    let mut stream = TcpStream::connect(address)
+        .with_context(|| format!("Could not connect to the coordinator at {}", address))?;
+    let config = match receive(&mut stream)? {
+        Request::Scene { config, directory } => Config::parse_str(&config, directory)
+            .with_context(|| "Error parsing the config sent by the coordinator")?,
+        _ => bail!("Expecting a scene from the coordinator."),
+    };
+
+    let materials = config.materials();
+    let hittables = config.world(&materials)?;
+    let world = Scene::build(&hittables).with_context(|| "Error building the BVH tree")?;
+    let tracer = Tracer::new(&config, &world)?;
+    let filter: Filter = (&config.image.filter).into();
+    let rng = FastRng::new(rand::random::<std::num::NonZeroU64>().into());
+    println!(
+        "Connected to {}, rendering a {}x{} image",
+        address, tracer.width, tracer.height
+    );
+
+    let mut tasks = 0;
+    loop {
+        match receive(&mut stream)? {
+            Request::Task { tile, samples } => {
+                let film = tracer.render_tile(tile, samples, filter.clone(), &rng);
+                send(&mut stream, &TaskResult::from(&film))?;
+                tasks += 1;
+            }
+            Request::Done => break,
+            Request::Scene { .. } => bail!("Received a second scene from the coordinator."),
+        }
+    }
+    println!("Rendered {} tasks.", tasks);
+    Ok(())
+}
@@ -29,6 +29,11 @@ impl SplitMix64 {
         Self(0x193a6754)
     }
 
+    /// The internal state, from which `new` creates an identical generator
+    pub const fn state(&self) -> u64 {
+        self.0
+    }
+
     /// Global random number generator with a unique state value.
     ///
     /// This is safe on 64-bits architectures, where u64 reads and writes
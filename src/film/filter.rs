@@ -0,0 +1,88 @@
+use std::f64::consts::PI;
+
+/// A pixel reconstruction filter, separable along the x and y axes.
+/// The radius is measured in pixels.
+#[derive(Clone, Debug)]
+pub enum Filter {
+    Box {
+        radius: f64,
+    },
+    Tent {
+        radius: f64,
+    },
+    Gaussian {
+        radius: f64,
+        alpha: f64,
+    },
+    /// Mitchell-Netravali filter, with the usual `b` and `c` parameters
+    Mitchell {
+        radius: f64,
+        b: f64,
+        c: f64,
+    },
+    /// Lanczos windowed sinc, with `radius` lobes
+    Lanczos {
+        radius: f64,
+    },
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self::Box { radius: 0.5 }
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-5 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+impl Filter {
+    pub fn radius(&self) -> f64 {
+        match self {
+            Self::Box { radius }
+            | Self::Tent { radius }
+            | Self::Gaussian { radius, .. }
+            | Self::Mitchell { radius, .. }
+            | Self::Lanczos { radius } => *radius,
+        }
+    }
+
+    /// The weight of a sample at an offset (x, y) from the center of a pixel
+    pub fn evaluate(&self, x: f64, y: f64) -> f64 {
+        self.evaluate_1d(x) * self.evaluate_1d(y)
+    }
+
+    fn evaluate_1d(&self, x: f64) -> f64 {
+        let x = x.abs();
+        if x > self.radius() {
+            return 0.0;
+        }
+        match self {
+            Self::Box { .. } => 1.0,
+            Self::Tent { radius } => radius - x,
+            Self::Gaussian { radius, alpha } => {
+                ((-alpha * x * x).exp() - (-alpha * radius * radius).exp()).max(0.0)
+            }
+            Self::Mitchell { radius, b, c } => {
+                let x = 2.0 * x / radius;
+                if x > 1.0 {
+                    ((-b - 6.0 * c) * x.powi(3)
+                        + (6.0 * b + 30.0 * c) * x * x
+                        + (-12.0 * b - 48.0 * c) * x
+                        + (8.0 * b + 24.0 * c))
+                        / 6.0
+                } else {
+                    ((12.0 - 9.0 * b - 6.0 * c) * x.powi(3)
+                        + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+                        + (6.0 - 2.0 * b))
+                        / 6.0
+                }
+            }
+            Self::Lanczos { radius } => sinc(x) * sinc(x / radius),
+        }
+    }
+}
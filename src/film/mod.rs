@@ -0,0 +1,206 @@
+pub mod filter;
+pub mod statistics;
+
+use std::sync::Mutex;
+
+use image::{ImageBuffer, Luma, Rgb};
+
+use crate::vec3::color::Colour;
+use filter::Filter;
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Pixel {
+    colour: Colour,
+    weight: f64,
+}
+
+/// Accumulates samples into pixels, weighted by a reconstruction filter.
+///
+/// Film coordinates are in pixels, from the top left corner of the image, with y
+/// pointing down. The pixel (i, j) covers [i, i + 1] x [j, j + 1]. Samples can land
+/// anywhere in the image, and are shared between threads.
+///
+/// A film can also cover only a window of the image, starting at the pixel `origin`.
+#[derive(Debug)]
+pub struct Film {
+    pub width: u32,
+    pub height: u32,
+    pub origin: (u32, u32),
+    pub filter: Filter,
+    pixels: Vec<Mutex<Pixel>>,
+}
+
+impl Film {
+    pub fn new(width: u32, height: u32, filter: Filter) -> Self {
+        Self {
+            width,
+            height,
+            origin: (0, 0),
+            filter,
+            pixels: (0..width * height)
+                .map(|_| Mutex::new(Pixel::default()))
+                .collect(),
+        }
+    }
+
+    /// Makes the film cover the window of the image starting at the pixel `origin`
+    pub fn with_origin(mut self, origin: (u32, u32)) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Adds a sample to all the pixels within the radius of the filter
+    pub fn add_sample(&self, x: f64, y: f64, colour: Colour) {
+        let radius = self.filter.radius();
+        // pixel centers are at half integer coordinates
+        let (x, y) = (
+            x - 0.5 - self.origin.0 as f64,
+            y - 0.5 - self.origin.1 as f64,
+        );
+        let min_i = (x - radius).ceil().max(0.0) as u32;
+        let max_i = ((x + radius).floor() as i64).min(self.width as i64 - 1);
+        let min_j = (y - radius).ceil().max(0.0) as u32;
+        let max_j = ((y + radius).floor() as i64).min(self.height as i64 - 1);
+
+        for j in min_j as i64..=max_j {
+            for i in min_i as i64..=max_i {
+                let weight = self.filter.evaluate(i as f64 - x, j as f64 - y);
+                if weight == 0.0 {
+                    continue;
+                }
+                let mut pixel = self.pixels[(j * self.width as i64 + i) as usize]
+                    .lock()
+                    .unwrap();
+                pixel.colour += colour * weight;
+                pixel.weight += weight;
+            }
+        }
+    }
+
+    /// The weighted sum of the samples and the total weight of each pixel
+    pub fn accumulated(&self) -> Vec<(Colour, f64)> {
+        self.pixels
+            .iter()
+            .map(|pixel| {
+                let pixel = pixel.lock().unwrap();
+                (pixel.colour, pixel.weight)
+            })
+            .collect()
+    }
+
+    /// Replaces the pixels with ones returned by `accumulated`
+    pub fn restore(&mut self, accumulated: &[(Colour, f64)]) {
+        for (pixel, &(colour, weight)) in self.pixels.iter_mut().zip(accumulated) {
+            *pixel.get_mut().unwrap() = Pixel { colour, weight };
+        }
+    }
+
+    /// Adds the samples accumulated in the pixels of another film covering a
+    /// window of this one, with the same filter
+    pub fn merge(&self, other: &Film) {
+        for (index, pixel) in other.pixels.iter().enumerate() {
+            let i = other.origin.0 + index as u32 % other.width;
+            let j = other.origin.1 + index as u32 / other.width;
+            if i < self.origin.0 || j < self.origin.1 {
+                continue;
+            }
+            let (i, j) = (i - self.origin.0, j - self.origin.1);
+            if i >= self.width || j >= self.height {
+                continue;
+            }
+            let other = *pixel.lock().unwrap();
+            let mut pixel = self.pixels[(j * self.width + i) as usize].lock().unwrap();
+            pixel.colour += other.colour;
+            pixel.weight += other.weight;
+        }
+    }
+
+    /// Normalises the pixels by their total weight, and converts them to 8 bit
+    /// colours with a gamma of 2
+    pub fn to_image(&self) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+        ImageBuffer::from_fn(self.width, self.height, |i, j| {
+            let pixel = *self.pixels[(j * self.width + i) as usize].lock().unwrap();
+            if pixel.weight <= 0.0 {
+                return Rgb([0, 0, 0]);
+            }
+            // filters with negative lobes can give slightly negative values
+            Rgb((pixel.colour / pixel.weight).max(Colour::BLACK).as_bytes(1))
+        })
+    }
+}
+
+/// Draws the number of samples taken in each pixel, from black for none to white
+/// for the largest count
+pub fn sample_count_image(
+    width: u32,
+    height: u32,
+    counts: &[u32],
+) -> ImageBuffer<Luma<u8>, Vec<u8>> {
+    let max = counts.iter().copied().max().unwrap_or(0).max(1);
+    ImageBuffer::from_fn(width, height, |i, j| {
+        Luma([(counts[(j * width + i) as usize] as f64 / max as f64 * 255.0).round() as u8])
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Film, Filter};
+    use crate::vec3::color::Colour;
+
+    #[test]
+    fn test_constant_image() {
+        // whatever the filter, a constant signal must give a constant image
+        let filters = [
+            Filter::Box { radius: 0.5 },
+            Filter::Tent { radius: 1.0 },
+            Filter::Gaussian {
+                radius: 1.5,
+                alpha: 2.0,
+            },
+            Filter::Mitchell {
+                radius: 2.0,
+                b: 1.0 / 3.0,
+                c: 1.0 / 3.0,
+            },
+            Filter::Lanczos { radius: 3.0 },
+        ];
+        for filter in filters {
+            let film = Film::new(4, 3, filter);
+            for j in 0..12 {
+                for i in 0..16 {
+                    let (x, y) = ((i as f64 + 0.5) / 4.0, (j as f64 + 0.5) / 4.0);
+                    film.add_sample(x, y, Colour::new(0.3, 0.3, 0.3));
+                }
+            }
+            for pixel in film.to_image().pixels() {
+                // sqrt(0.3) * 256 = 140.2
+                assert_eq!(pixel.0, [140, 140, 140]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_merge_window() {
+        // samples splatted into windows of the image and merged give the same
+        // image as when splatted into the whole film
+        let filter = Filter::Tent { radius: 1.0 };
+        let whole = Film::new(6, 4, filter.clone());
+        let merged = Film::new(6, 4, filter.clone());
+        let windows = [
+            Film::new(5, 4, filter.clone()),
+            Film::new(4, 4, filter).with_origin((2, 0)),
+        ];
+        for j in 0..4 {
+            for i in 0..6 {
+                let (x, y) = (i as f64 + 0.3, j as f64 + 0.6);
+                let colour = Colour::new(i as f64 / 6.0, j as f64 / 4.0, 0.5);
+                whole.add_sample(x, y, colour);
+                windows[(i >= 3) as usize].add_sample(x, y, colour);
+            }
+        }
+        for window in &windows {
+            merged.merge(window);
+        }
+        assert_eq!(whole.to_image(), merged.to_image());
+    }
+}
@@ -0,0 +1,34 @@
+/// Luminance below which the error of a pixel is measured in absolute terms, so
+/// that dark pixels do not need an unbounded number of samples
+const DARK_LUMINANCE: f64 = 0.01;
+
+/// Running mean and variance of the luminance of the samples of a pixel,
+/// with the algorithm of Welford
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PixelStatistics {
+    pub count: u32,
+    pub mean: f64,
+    /// The sum of the squared differences to the mean
+    pub m2: f64,
+}
+
+impl PixelStatistics {
+    pub fn add(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    pub fn variance(&self) -> f64 {
+        if self.count < 2 {
+            return f64::INFINITY;
+        }
+        self.m2 / (self.count - 1) as f64
+    }
+
+    /// The standard error of the mean, relative to the mean
+    pub fn relative_error(&self) -> f64 {
+        (self.variance() / self.count as f64).sqrt() / self.mean.abs().max(DARK_LUMINANCE)
+    }
+}
@@ -0,0 +1,142 @@
+use std::f64::consts::FRAC_1_PI;
+
+use crate::bounding_boxes::BoundingBox;
+use crate::hittable::disk::Disk;
+use crate::hittable::{Hit, HitRecord, Hittable};
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+use crate::FastRng;
+
+#[derive(Debug, Clone)]
+pub struct Cone<'a> {
+    pub base: Point3,
+    pub axis: Vec3,
+    pub height: f64,
+    pub radius: f64,
+    pub capped: bool,
+    pub frame: (Vec3, Vec3),
+    pub material: &'a (dyn Material + 'a),
+}
+
+impl<'a> Cone<'a> {
+    pub fn new(
+        base: Point3,
+        apex: Point3,
+        radius: f64,
+        capped: bool,
+        material: &'a (dyn Material + 'a),
+    ) -> Self {
+        let height = (apex - base).length();
+        let axis = (apex - base) / height;
+        Self {
+            base,
+            axis,
+            height,
+            radius,
+            capped,
+            frame: axis.orthonormal_basis(),
+            material,
+        }
+    }
+
+    /// Expresses a vector in the local frame of the cone,
+    /// where the z coordinate is along the axis
+    fn to_local(&self, v: Vec3) -> Vec3 {
+        Vec3::new(v.dot(self.frame.0), v.dot(self.frame.1), v.dot(self.axis))
+    }
+}
+
+impl<'a> Hittable for Cone<'a> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, _: &mut FastRng) -> Hit {
+        let origin = self.to_local(ray.origin - self.base);
+        let direction = self.to_local(ray.direction);
+
+        let mut closest = t_max;
+        let mut record = None;
+
+        // lateral surface: x² + y² = k²(h - z)², with k = r / h
+        let k2 = (self.radius / self.height).powi(2);
+        let dz = self.height - origin.z;
+        let a =
+            direction.x * direction.x + direction.y * direction.y - k2 * direction.z * direction.z;
+        let half_b = origin.x * direction.x + origin.y * direction.y + k2 * dz * direction.z;
+        let c = origin.x * origin.x + origin.y * origin.y - k2 * dz * dz;
+
+        let roots = if a.abs() < 1e-12_f64 {
+            // the ray is parallel to the surface of the cone
+            if half_b.abs() < 1e-12_f64 {
+                [f64::NAN, f64::NAN]
+            } else {
+                [-0.5 * c / half_b, f64::NAN]
+            }
+        } else {
+            let delta = half_b * half_b - a * c;
+            if delta < 0.0 {
+                [f64::NAN, f64::NAN]
+            } else {
+                let sqrtd = delta.sqrt();
+                let (r1, r2) = ((-half_b - sqrtd) / a, (-half_b + sqrtd) / a);
+                [r1.min(r2), r1.max(r2)]
+            }
+        };
+
+        for root in roots {
+            // NaN roots fail both comparisons and are skipped here
+            if !(root >= t_min && root <= closest) {
+                continue;
+            }
+            let local = origin + direction * root;
+            if local.z < 0.0 || local.z > self.height {
+                continue;
+            }
+            let outward_normal = (self.frame.0 * local.x
+                + self.frame.1 * local.y
+                + self.axis * (k2 * (self.height - local.z)))
+                .unit_vector();
+            let u = 0.5 + 0.5 * local.y.atan2(local.x) * FRAC_1_PI;
+            closest = root;
+            record = Some(HitRecord::new(
+                ray.at(root),
+                outward_normal,
+                root,
+                (u, local.z / self.height),
+                ray,
+                self.material,
+            ));
+            break;
+        }
+
+        // base cap: z = 0
+        if self.capped && direction.z.abs() > 1e-12_f64 {
+            let root = -origin.z / direction.z;
+            if root >= t_min && root <= closest {
+                let local = origin + direction * root;
+                if local.x * local.x + local.y * local.y <= self.radius * self.radius {
+                    record = Some(HitRecord::new(
+                        ray.at(root),
+                        -self.axis,
+                        root,
+                        (
+                            0.5 + 0.5 * local.x / self.radius,
+                            0.5 + 0.5 * local.y / self.radius,
+                        ),
+                        ray,
+                        self.material,
+                    ));
+                }
+            }
+        }
+
+        record
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        let apex = self.base + self.axis * self.height;
+        let base_box = Disk::disk_bounding_box(self.base, self.axis, self.radius);
+        BoundingBox {
+            minimum: base_box.minimum.min(apex - 1e-7f64),
+            maximum: base_box.maximum.max(apex + 1e-7f64),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::bounding_boxes::BoundingBox;
+use crate::hittable::{Hit, HitRecord, Hittable};
+use crate::ray::Ray;
+use crate::FastRng;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operation {
+    Union,
+    Intersection,
+    /// Removes the right object from the left one
+    Difference,
+}
+
+impl Operation {
+    fn combine(self, inside_left: bool, inside_right: bool) -> bool {
+        match self {
+            Operation::Union => inside_left || inside_right,
+            Operation::Intersection => inside_left && inside_right,
+            Operation::Difference => inside_left && !inside_right,
+        }
+    }
+}
+
+/// Constructive solid geometry node. Both children must be closed objects.
+#[derive(Debug)]
+pub struct Csg<'a> {
+    pub operation: Operation,
+    pub left: Box<dyn Hittable + 'a>,
+    pub right: Box<dyn Hittable + 'a>,
+}
+
+impl<'a> Csg<'a> {
+    pub fn new(
+        operation: Operation,
+        left: Box<dyn Hittable + 'a>,
+        right: Box<dyn Hittable + 'a>,
+    ) -> Self {
+        Self {
+            operation,
+            left,
+            right,
+        }
+    }
+}
+
+impl<'a> Hittable for Csg<'a> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rng: &mut FastRng) -> Hit {
+        self.all_hits(ray, t_min, t_max, rng).into_iter().next()
+    }
+
+    fn all_hits(&self, ray: &Ray, t_min: f64, t_max: f64, rng: &mut FastRng) -> Vec<HitRecord> {
+        // The children are queried up to infinity, so that the first
+        // intersection tells whether the ray starts inside of them.
+        let left = self.left.all_hits(ray, t_min, f64::INFINITY, rng);
+        let right = self.right.all_hits(ray, t_min, f64::INFINITY, rng);
+
+        let mut inside_left = left.first().map(|r| !r.front_face).unwrap_or(false);
+        let mut inside_right = right.first().map(|r| !r.front_face).unwrap_or(false);
+        let mut inside = self.operation.combine(inside_left, inside_right);
+
+        let mut hits = Vec::new();
+        let (mut left, mut right) = (left.into_iter().peekable(), right.into_iter().peekable());
+        loop {
+            let from_left = match (left.peek(), right.peek()) {
+                (Some(a), Some(b)) => a.t <= b.t,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => break,
+            };
+            let mut record = if from_left {
+                let record = left.next().unwrap();
+                inside_left = record.front_face;
+                record
+            } else {
+                let record = right.next().unwrap();
+                inside_right = record.front_face;
+                record
+            };
+            if record.t > t_max {
+                break;
+            }
+
+            let now_inside = self.operation.combine(inside_left, inside_right);
+            if now_inside != inside {
+                // the normal of a record always faces the ray,
+                // so only the side of the surface needs fixing
+                record.front_face = now_inside;
+                hits.push(record);
+                inside = now_inside;
+            }
+        }
+        hits
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        let left = self.left.bounding_box();
+        match self.operation {
+            Operation::Union => left.join(&self.right.bounding_box()),
+            Operation::Intersection => {
+                let right = self.right.bounding_box();
+                BoundingBox {
+                    minimum: left.minimum.max(right.minimum),
+                    maximum: left.maximum.min(right.maximum),
+                }
+            }
+            Operation::Difference => left,
+        }
+    }
+}
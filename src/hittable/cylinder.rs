@@ -0,0 +1,127 @@
+use std::f64::consts::FRAC_1_PI;
+
+use crate::bounding_boxes::BoundingBox;
+use crate::hittable::disk::Disk;
+use crate::hittable::{Hit, HitRecord, Hittable};
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+use crate::FastRng;
+
+#[derive(Debug, Clone)]
+pub struct Cylinder<'a> {
+    pub base: Point3,
+    pub axis: Vec3,
+    pub height: f64,
+    pub radius: f64,
+    pub capped: bool,
+    pub frame: (Vec3, Vec3),
+    pub material: &'a (dyn Material + 'a),
+}
+
+impl<'a> Cylinder<'a> {
+    pub fn new(
+        base: Point3,
+        top: Point3,
+        radius: f64,
+        capped: bool,
+        material: &'a (dyn Material + 'a),
+    ) -> Self {
+        let height = (top - base).length();
+        let axis = (top - base) / height;
+        Self {
+            base,
+            axis,
+            height,
+            radius,
+            capped,
+            frame: axis.orthonormal_basis(),
+            material,
+        }
+    }
+
+    /// Expresses a vector in the local frame of the cylinder,
+    /// where the z coordinate is along the axis
+    fn to_local(&self, v: Vec3) -> Vec3 {
+        Vec3::new(v.dot(self.frame.0), v.dot(self.frame.1), v.dot(self.axis))
+    }
+}
+
+impl<'a> Hittable for Cylinder<'a> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, _: &mut FastRng) -> Hit {
+        let origin = self.to_local(ray.origin - self.base);
+        let direction = self.to_local(ray.direction);
+
+        let mut closest = t_max;
+        let mut record = None;
+
+        // lateral surface: x² + y² = r²
+        let a = direction.x * direction.x + direction.y * direction.y;
+        if a > 1e-12_f64 {
+            let half_b = origin.x * direction.x + origin.y * direction.y;
+            let c = origin.x * origin.x + origin.y * origin.y - self.radius * self.radius;
+            let delta = half_b * half_b - a * c;
+            if delta >= 0.0 {
+                let sqrtd = delta.sqrt();
+                for root in [(-half_b - sqrtd) / a, (-half_b + sqrtd) / a] {
+                    if root < t_min || root > closest {
+                        continue;
+                    }
+                    let local = origin + direction * root;
+                    if local.z < 0.0 || local.z > self.height {
+                        continue;
+                    }
+                    let outward_normal =
+                        (self.frame.0 * local.x + self.frame.1 * local.y) / self.radius;
+                    let u = 0.5 + 0.5 * local.y.atan2(local.x) * FRAC_1_PI;
+                    closest = root;
+                    record = Some(HitRecord::new(
+                        ray.at(root),
+                        outward_normal,
+                        root,
+                        (u, local.z / self.height),
+                        ray,
+                        self.material,
+                    ));
+                    break;
+                }
+            }
+        }
+
+        // end caps: z = 0 and z = height
+        if self.capped && direction.z.abs() > 1e-12_f64 {
+            for (z, outward_normal) in [(0.0, -self.axis), (self.height, self.axis)] {
+                let root = (z - origin.z) / direction.z;
+                if root < t_min || root > closest {
+                    continue;
+                }
+                let local = origin + direction * root;
+                if local.x * local.x + local.y * local.y > self.radius * self.radius {
+                    continue;
+                }
+                closest = root;
+                record = Some(HitRecord::new(
+                    ray.at(root),
+                    outward_normal,
+                    root,
+                    (
+                        0.5 + 0.5 * local.x / self.radius,
+                        0.5 + 0.5 * local.y / self.radius,
+                    ),
+                    ray,
+                    self.material,
+                ));
+            }
+        }
+
+        record
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        Disk::disk_bounding_box(self.base, self.axis, self.radius).join(&Disk::disk_bounding_box(
+            self.base + self.axis * self.height,
+            self.axis,
+            self.radius,
+        ))
+    }
+}
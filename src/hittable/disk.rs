@@ -0,0 +1,78 @@
+use crate::bounding_boxes::BoundingBox;
+use crate::hittable::{Hit, HitRecord, Hittable};
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+use crate::FastRng;
+
+#[derive(Debug, Clone)]
+pub struct Disk<'a> {
+    pub center: Point3,
+    pub normal: Vec3,
+    pub radius: f64,
+    pub base: (Vec3, Vec3),
+    pub material: &'a (dyn Material + 'a),
+}
+
+impl<'a> Disk<'a> {
+    pub fn new(
+        center: Point3,
+        normal: Vec3,
+        radius: f64,
+        material: &'a (dyn Material + 'a),
+    ) -> Self {
+        let normal = normal.unit_vector();
+        Self {
+            center,
+            normal,
+            radius,
+            base: normal.orthonormal_basis(),
+            material,
+        }
+    }
+
+    /// Bounding box of a disk of radius `radius` and unit normal `normal`
+    pub fn disk_bounding_box(center: Point3, normal: Vec3, radius: f64) -> BoundingBox {
+        let extent = Vec3::new(
+            (1.0 - normal.x * normal.x).max(0.0).sqrt(),
+            (1.0 - normal.y * normal.y).max(0.0).sqrt(),
+            (1.0 - normal.z * normal.z).max(0.0).sqrt(),
+        ) * radius
+            + 1e-7f64;
+        BoundingBox {
+            minimum: center - extent,
+            maximum: center + extent,
+        }
+    }
+}
+
+impl<'a> Hittable for Disk<'a> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, _: &mut FastRng) -> Hit {
+        let determinant = self.normal.dot(ray.direction);
+        if determinant.abs() < 1e-8_f64 {
+            return None;
+        }
+        let time = self.normal.dot(self.center - ray.origin) / determinant;
+        if time < t_min || time > t_max {
+            return None;
+        }
+        let point = ray.at(time);
+        let local = point - self.center;
+        let (x, y) = (local.dot(self.base.0), local.dot(self.base.1));
+        if x * x + y * y > self.radius * self.radius {
+            return None;
+        }
+        Some(HitRecord::new(
+            point,
+            self.normal,
+            time,
+            (0.5 + 0.5 * x / self.radius, 0.5 + 0.5 * y / self.radius),
+            ray,
+            self.material,
+        ))
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        Self::disk_bounding_box(self.center, self.normal, self.radius)
+    }
+}
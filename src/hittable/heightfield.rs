@@ -0,0 +1,197 @@
+use crate::bounding_boxes::BoundingBox;
+use crate::hittable::{Hit, HitRecord, Hittable};
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+use crate::FastRng;
+
+/// A terrain defined by a grid of heights, spanning `size.0` along the x axis
+/// and `size.1` along the z axis from `origin`.
+///
+/// Each grid cell is split in two triangles, and rays traverse the grid cell by
+/// cell so that only the triangles along the path of the ray are tested.
+#[derive(Debug, Clone)]
+pub struct Heightfield<'a> {
+    pub origin: Point3,
+    pub size: (f64, f64),
+    pub resolution: (usize, usize),
+    pub heights: Vec<f64>,
+    pub normals: Vec<Vec3>,
+    pub bounds: BoundingBox,
+    pub material: &'a (dyn Material + 'a),
+}
+
+impl<'a> Heightfield<'a> {
+    /// Creates a heightfield from a grid of `resolution.0 * resolution.1` heights
+    /// stored row by row, each row being along the x axis.
+    pub fn new(
+        origin: Point3,
+        size: (f64, f64),
+        resolution: (usize, usize),
+        heights: Vec<f64>,
+        material: &'a (dyn Material + 'a),
+    ) -> Self {
+        let (nx, nz) = resolution;
+        assert!(
+            nx >= 2 && nz >= 2,
+            "A heightfield needs at least 2x2 samples"
+        );
+        assert_eq!(heights.len(), nx * nz);
+        let (dx, dz) = (size.0 / (nx - 1) as f64, size.1 / (nz - 1) as f64);
+
+        let height = |i: usize, j: usize| heights[j * nx + i];
+        let normals = (0..nz)
+            .flat_map(|j| (0..nx).map(move |i| (i, j)))
+            .map(|(i, j)| {
+                let (i0, i1) = (i.saturating_sub(1), (i + 1).min(nx - 1));
+                let (j0, j1) = (j.saturating_sub(1), (j + 1).min(nz - 1));
+                let slope_x = (height(i1, j) - height(i0, j)) / ((i1 - i0) as f64 * dx);
+                let slope_z = (height(i, j1) - height(i, j0)) / ((j1 - j0) as f64 * dz);
+                Vec3::new(-slope_x, 1.0, -slope_z).unit_vector()
+            })
+            .collect();
+
+        let (min_height, max_height) = heights
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &h| {
+                (min.min(h), max.max(h))
+            });
+        let bounds = BoundingBox {
+            minimum: origin + Vec3::new(0.0, min_height, 0.0) - 1e-7f64,
+            maximum: origin + Vec3::new(size.0, max_height, size.1) + 1e-7f64,
+        };
+
+        Self {
+            origin,
+            size,
+            resolution,
+            heights,
+            normals,
+            bounds,
+            material,
+        }
+    }
+
+    fn cell_size(&self) -> (f64, f64) {
+        (
+            self.size.0 / (self.resolution.0 - 1) as f64,
+            self.size.1 / (self.resolution.1 - 1) as f64,
+        )
+    }
+
+    fn vertex(&self, i: usize, j: usize) -> (Point3, Vec3) {
+        let (dx, dz) = self.cell_size();
+        let index = j * self.resolution.0 + i;
+        (
+            self.origin + Vec3::new(i as f64 * dx, self.heights[index], j as f64 * dz),
+            self.normals[index],
+        )
+    }
+
+    /// Intersects the two triangles of the cell (i, j)
+    fn hit_cell(&self, ray: &Ray, i: usize, j: usize, t_min: f64, t_max: f64) -> Hit {
+        let a = self.vertex(i, j);
+        let b = self.vertex(i + 1, j);
+        let c = self.vertex(i, j + 1);
+        let d = self.vertex(i + 1, j + 1);
+
+        let mut closest = t_max;
+        let mut record = None;
+        for (v0, v1, v2) in [(a, c, b), (b, c, d)] {
+            let e1 = v1.0 - v0.0;
+            let e2 = v2.0 - v0.0;
+            let p = ray.direction.cross(e2);
+            let determinant = e1.dot(p);
+            if determinant.abs() < 1e-12_f64 {
+                continue;
+            }
+            let inv_determinant = 1.0 / determinant;
+            let s = ray.origin - v0.0;
+            let lambda = s.dot(p) * inv_determinant;
+            if !(0.0..=1.0).contains(&lambda) {
+                continue;
+            }
+            let q = s.cross(e1);
+            let mu = ray.direction.dot(q) * inv_determinant;
+            if mu < 0.0 || lambda + mu > 1.0 {
+                continue;
+            }
+            let time = e2.dot(q) * inv_determinant;
+            if time < t_min || time > closest {
+                continue;
+            }
+            let point = ray.at(time);
+            let normal = (v0.1 * (1.0 - lambda - mu) + v1.1 * lambda + v2.1 * mu).unit_vector();
+            closest = time;
+            record = Some(HitRecord::new(
+                point,
+                normal,
+                time,
+                (
+                    (point.x - self.origin.x) / self.size.0,
+                    (point.z - self.origin.z) / self.size.1,
+                ),
+                ray,
+                self.material,
+            ));
+        }
+        record
+    }
+}
+
+impl<'a> Hittable for Heightfield<'a> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, _: &mut FastRng) -> Hit {
+        let (enter, exit) = self.bounds.intersect(ray, t_min, t_max)?;
+        let (dx, dz) = self.cell_size();
+        let (max_i, max_j) = (self.resolution.0 as i64 - 2, self.resolution.1 as i64 - 2);
+
+        // 2D DDA over the cells of the grid, starting from the cell where
+        // the ray enters the bounding box
+        let start = ray.at(enter) - self.origin;
+        let mut i = ((start.x / dx).floor() as i64).clamp(0, max_i);
+        let mut j = ((start.z / dz).floor() as i64).clamp(0, max_j);
+
+        let setup = |cell: i64, cell_size: f64, origin: f64, direction: f64| {
+            if direction.abs() < 1e-12_f64 {
+                (0, f64::INFINITY, f64::INFINITY)
+            } else {
+                let step = if direction > 0.0 { 1 } else { 0 };
+                let boundary = (cell + step) as f64 * cell_size;
+                (
+                    if direction > 0.0 { 1 } else { -1 },
+                    (boundary - origin) / direction,
+                    cell_size / direction.abs(),
+                )
+            }
+        };
+        let local_origin = ray.origin - self.origin;
+        let (step_i, mut next_i, delta_i) = setup(i, dx, local_origin.x, ray.direction.x);
+        let (step_j, mut next_j, delta_j) = setup(j, dz, local_origin.z, ray.direction.z);
+
+        loop {
+            if let Some(record) = self.hit_cell(ray, i as usize, j as usize, t_min, t_max) {
+                return Some(record);
+            }
+            if next_i < next_j {
+                if next_i > exit {
+                    return None;
+                }
+                i += step_i;
+                next_i += delta_i;
+            } else {
+                if next_j > exit {
+                    return None;
+                }
+                j += step_j;
+                next_j += delta_j;
+            }
+            if i < 0 || j < 0 || i > max_i || j > max_j {
+                return None;
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        self.bounds.clone()
+    }
+}
@@ -17,7 +17,7 @@ impl<'a> Hittable for HittableList<'a> {
             let tmp = object.hit(ray, t_min, closest, rng);
 
             if let Some(r) = tmp {
-                closest = r.time;
+                closest = r.t;
                 record = Some(r);
             }
         }
@@ -0,0 +1,177 @@
+use crate::bounding_boxes::BoundingBox;
+use crate::hittable::{Hit, Hittable};
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+use crate::FastRng;
+
+/// Number of samples taken between two keyframes to bound the motion of an instance
+const MOTION_SAMPLES: usize = 32;
+
+#[derive(Debug, Clone)]
+pub struct Keyframe {
+    pub time: f64,
+    pub translation: Vec3,
+    /// Euler angles in degrees, applied around the x, y then z axes
+    pub rotation: Vec3,
+    pub scale: f64,
+}
+
+/// A similarity transform: uniform scale, then rotation, then translation
+#[derive(Debug, Clone)]
+struct Transform {
+    translation: Vec3,
+    /// The columns of the rotation matrix
+    rotation: (Vec3, Vec3, Vec3),
+    scale: f64,
+}
+
+impl Transform {
+    fn new(keyframe: &Keyframe) -> Self {
+        let (sx, cx) = keyframe.rotation.x.to_radians().sin_cos();
+        let (sy, cy) = keyframe.rotation.y.to_radians().sin_cos();
+        let (sz, cz) = keyframe.rotation.z.to_radians().sin_cos();
+        // columns of Rz * Ry * Rx
+        let rotation = (
+            Vec3::new(cy * cz, cy * sz, -sy),
+            Vec3::new(sx * sy * cz - cx * sz, sx * sy * sz + cx * cz, sx * cy),
+            Vec3::new(cx * sy * cz + sx * sz, cx * sy * sz - sx * cz, cx * cy),
+        );
+        Self {
+            translation: keyframe.translation,
+            rotation,
+            scale: keyframe.scale,
+        }
+    }
+
+    fn rotate(&self, v: Vec3) -> Vec3 {
+        self.rotation.0 * v.x + self.rotation.1 * v.y + self.rotation.2 * v.z
+    }
+
+    fn inverse_rotate(&self, v: Vec3) -> Vec3 {
+        Vec3::new(
+            self.rotation.0.dot(v),
+            self.rotation.1.dot(v),
+            self.rotation.2.dot(v),
+        )
+    }
+
+    fn apply(&self, point: Point3) -> Point3 {
+        self.rotate(point * self.scale) + self.translation
+    }
+}
+
+/// An object moved by a keyframed transform. Between keyframes, the translation,
+/// rotation angles and scale are linearly interpolated; before the first and after
+/// the last keyframe, the object stays still.
+#[derive(Debug)]
+pub struct Instance<'a> {
+    pub object: Box<dyn Hittable + 'a>,
+    pub keyframes: Vec<Keyframe>,
+    bounds: BoundingBox,
+}
+
+impl<'a> Instance<'a> {
+    pub fn new(object: Box<dyn Hittable + 'a>, mut keyframes: Vec<Keyframe>) -> Self {
+        assert!(
+            !keyframes.is_empty(),
+            "An instance needs at least one keyframe"
+        );
+        keyframes.sort_unstable_by(|a, b| a.time.total_cmp(&b.time));
+        let mut instance = Self {
+            object,
+            keyframes,
+            bounds: BoundingBox::INFINITE,
+        };
+        instance.bounds = instance.motion_bounds();
+        instance
+    }
+
+    fn keyframe(&self, time: f64) -> Keyframe {
+        let first = &self.keyframes[0];
+        let last = &self.keyframes[self.keyframes.len() - 1];
+        if time <= first.time {
+            return first.clone();
+        }
+        if time >= last.time {
+            return last.clone();
+        }
+        let next = self.keyframes.iter().position(|k| k.time > time).unwrap();
+        let (a, b) = (&self.keyframes[next - 1], &self.keyframes[next]);
+        let h = (time - a.time) / (b.time - a.time);
+        Keyframe {
+            time,
+            translation: a.translation * (1.0 - h) + b.translation * h,
+            rotation: a.rotation * (1.0 - h) + b.rotation * h,
+            scale: a.scale * (1.0 - h) + b.scale * h,
+        }
+    }
+
+    /// Bounds the object over its whole motion, by sweeping the bounding sphere
+    /// of the object along its trajectory
+    fn motion_bounds(&self) -> BoundingBox {
+        let object_bounds = self.object.bounding_box();
+        if !object_bounds.is_finite() {
+            return object_bounds;
+        }
+        let center = (object_bounds.minimum + object_bounds.maximum) * 0.5;
+        let radius = (object_bounds.maximum - center).length();
+
+        let mut times = vec![self.keyframes[0].time];
+        for pair in self.keyframes.windows(2) {
+            for step in 1..=MOTION_SAMPLES {
+                let h = step as f64 / MOTION_SAMPLES as f64;
+                times.push(pair[0].time * (1.0 - h) + pair[1].time * h);
+            }
+        }
+
+        // between two samples, the rotated center deviates from the straight
+        // line by at most |center| * (1 - cos(angle / 2))
+        let max_angle = self
+            .keyframes
+            .windows(2)
+            .map(|pair| {
+                let delta = pair[1].rotation - pair[0].rotation;
+                (delta.x.abs() + delta.y.abs() + delta.z.abs()).to_radians() / MOTION_SAMPLES as f64
+            })
+            .fold(0.0, f64::max);
+        let max_scale = self.keyframes.iter().map(|k| k.scale).fold(0.0, f64::max);
+        let margin = center.length() * max_scale * (1.0 - (0.5 * max_angle).cos());
+
+        times
+            .into_iter()
+            .map(|time| {
+                let transform = Transform::new(&self.keyframe(time));
+                let extent = radius * transform.scale + margin + 1e-7f64;
+                let center = transform.apply(center);
+                BoundingBox {
+                    minimum: center - extent,
+                    maximum: center + extent,
+                }
+            })
+            .reduce(|a, b| a.join(&b))
+            .unwrap()
+    }
+}
+
+impl<'a> Hittable for Instance<'a> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rng: &mut FastRng) -> Hit {
+        let transform = Transform::new(&self.keyframe(ray.time));
+        // the transform is affine, so the ray parameter t is preserved
+        let local_ray = Ray::new(
+            transform.inverse_rotate(ray.origin - transform.translation) / transform.scale,
+            transform.inverse_rotate(ray.direction) / transform.scale,
+            ray.time,
+        );
+        self.object
+            .hit(&local_ray, t_min, t_max, rng)
+            .map(|mut record| {
+                record.point = transform.apply(record.point);
+                record.normal = transform.rotate(record.normal);
+                record
+            })
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        self.bounds.clone()
+    }
+}
@@ -1,6 +1,16 @@
+pub mod cone;
+pub mod csg;
+pub mod cylinder;
+pub mod disk;
+pub mod heightfield;
 pub mod hittable_list;
+pub mod instance;
+pub mod moving_sphere;
+pub mod plane;
 pub mod quad;
+pub mod sdf;
 pub mod sphere;
+pub mod torus;
 pub mod triangle;
 pub mod volumetric;
 
@@ -16,7 +26,8 @@ use crate::FastRng;
 pub struct HitRecord<'a> {
     pub point: Point3,
     pub normal: Vec3,
-    pub time: f64,
+    pub t: f64,
+    pub uv: (f64, f64),
     pub front_face: bool,
     pub material: &'a (dyn Material + 'a),
 }
@@ -27,7 +38,8 @@ impl<'a> HitRecord<'a> {
     pub fn new(
         point: Point3,
         outward_normal: Vec3,
-        time: f64,
+        t: f64,
+        uv: (f64, f64),
         ray: &Ray,
         material: &'a (dyn Material + 'a),
     ) -> Self {
@@ -41,7 +53,8 @@ impl<'a> HitRecord<'a> {
         Self {
             point,
             normal,
-            time,
+            t,
+            uv,
             front_face,
             material,
         }
@@ -51,5 +64,18 @@ impl<'a> HitRecord<'a> {
 pub trait Hittable: Debug + Send + Sync {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rng: &mut FastRng) -> Hit;
 
+    /// Returns every intersection of the ray with the object between `t_min`
+    /// and `t_max`, sorted by distance. For closed objects, the `front_face` field of
+    /// each record tells whether the ray enters or exits the object.
+    fn all_hits(&self, ray: &Ray, t_min: f64, t_max: f64, rng: &mut FastRng) -> Vec<HitRecord> {
+        let mut hits = Vec::new();
+        let mut t_min = t_min;
+        while let Some(record) = self.hit(ray, t_min, t_max, rng) {
+            t_min = record.t + 1e-7;
+            hits.push(record);
+        }
+        hits
+    }
+
     fn bounding_box(&self) -> BoundingBox;
 }
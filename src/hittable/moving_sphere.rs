@@ -0,0 +1,72 @@
+use crate::bounding_boxes::BoundingBox;
+use crate::hittable::sphere::Sphere;
+use crate::hittable::{Hit, HitRecord, Hittable};
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::vec3::Point3;
+use crate::FastRng;
+
+/// A sphere moving in a straight line from `centers.0` at time 0
+/// to `centers.1` at time 1. It stays still outside of this interval.
+#[derive(Debug, Clone)]
+pub struct MovingSphere<'a> {
+    pub centers: (Point3, Point3),
+    pub radius: f64,
+    pub material: &'a (dyn Material + 'a),
+}
+
+impl<'a> MovingSphere<'a> {
+    pub fn new(centers: (Point3, Point3), radius: f64, material: &'a (dyn Material + 'a)) -> Self {
+        Self {
+            centers,
+            radius,
+            material,
+        }
+    }
+
+    pub fn center(&self, time: f64) -> Point3 {
+        let time = time.clamp(0.0, 1.0);
+        self.centers.0 * (1.0 - time) + self.centers.1 * time
+    }
+}
+
+impl<'a> Hittable for MovingSphere<'a> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, _: &mut FastRng) -> Hit {
+        let center = self.center(ray.time);
+        let oc = ray.origin - center;
+        let a = ray.direction.length_squared();
+        let half_b = oc.dot(ray.direction);
+        let c = oc.length_squared() - self.radius * self.radius;
+        let delta = half_b.powi(2) - a * c;
+        if delta < 0.0 {
+            return None;
+        }
+
+        let sqrtd = delta.sqrt();
+        let mut root = (-half_b - sqrtd) / a;
+        if root < t_min || root > t_max {
+            root = (-half_b + sqrtd) / a;
+            if root < t_min || root > t_max {
+                return None;
+            }
+        }
+
+        let point = ray.at(root);
+        let outward_normal = (point - center) / self.radius;
+        Some(HitRecord::new(
+            point,
+            outward_normal,
+            root,
+            Sphere::uv(outward_normal),
+            ray,
+            self.material,
+        ))
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox {
+            minimum: self.centers.0.min(self.centers.1) - self.radius,
+            maximum: self.centers.0.max(self.centers.1) + self.radius,
+        }
+    }
+}
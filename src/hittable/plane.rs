@@ -0,0 +1,57 @@
+use crate::bounding_boxes::BoundingBox;
+use crate::hittable::{Hit, HitRecord, Hittable};
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+use crate::FastRng;
+
+/// An infinite plane. It has no finite bounding box, so it is kept
+/// outside of the BVH by [`crate::bounding_boxes::Scene`].
+#[derive(Debug, Clone)]
+pub struct Plane<'a> {
+    pub point: Point3,
+    pub normal: Vec3,
+    pub base: (Vec3, Vec3),
+    pub material: &'a (dyn Material + 'a),
+}
+
+impl<'a> Plane<'a> {
+    pub fn new(point: Point3, normal: Vec3, material: &'a (dyn Material + 'a)) -> Self {
+        let normal = normal.unit_vector();
+        Self {
+            point,
+            normal,
+            base: normal.orthonormal_basis(),
+            material,
+        }
+    }
+}
+
+impl<'a> Hittable for Plane<'a> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, _: &mut FastRng) -> Hit {
+        let determinant = self.normal.dot(ray.direction);
+        if determinant.abs() < 1e-8_f64 {
+            return None;
+        }
+        let time = self.normal.dot(self.point - ray.origin) / determinant;
+        if time < t_min || time > t_max {
+            return None;
+        }
+        let point = ray.at(time);
+        let local = point - self.point;
+        // the uv coordinates are world space distances along the plane,
+        // so that periodic textures tile across it
+        Some(HitRecord::new(
+            point,
+            self.normal,
+            time,
+            (local.dot(self.base.0), local.dot(self.base.1)),
+            ray,
+            self.material,
+        ))
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::INFINITE
+    }
+}
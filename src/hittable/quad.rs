@@ -58,6 +58,7 @@ impl<'a> Hittable for Quad<'a> {
                     point,
                     normal.unit_vector(),
                     time,
+                    (lambda, mu),
                     ray,
                     self.material,
                 ))
@@ -0,0 +1,272 @@
+use crate::bounding_boxes::BoundingBox;
+use crate::hittable::{Hit, HitRecord, Hittable};
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+use crate::FastRng;
+
+const MAX_STEPS: usize = 512;
+const HIT_DISTANCE: f64 = 1e-5;
+const NORMAL_EPSILON: f64 = 1e-6;
+
+/// A tree of signed distance functions and operators
+#[derive(Debug, Clone)]
+pub enum DistanceField {
+    Sphere {
+        center: Point3,
+        radius: f64,
+    },
+    Box {
+        center: Point3,
+        half_extents: Vec3,
+    },
+    RoundBox {
+        center: Point3,
+        half_extents: Vec3,
+        radius: f64,
+    },
+    /// A torus lying in the xz plane
+    Torus {
+        center: Point3,
+        major_radius: f64,
+        minor_radius: f64,
+    },
+    Capsule {
+        a: Point3,
+        b: Point3,
+        radius: f64,
+    },
+    Mandelbulb {
+        center: Point3,
+        scale: f64,
+        power: f64,
+        iterations: u32,
+    },
+    /// Union of two shapes, blended over a distance `smoothness`
+    Union {
+        left: Box<DistanceField>,
+        right: Box<DistanceField>,
+        smoothness: f64,
+    },
+    /// Removes the right shape from the left one, blended over a distance `smoothness`
+    Subtraction {
+        left: Box<DistanceField>,
+        right: Box<DistanceField>,
+        smoothness: f64,
+    },
+    /// Infinite repetition of a shape. A null period disables repetition along this axis.
+    Repetition {
+        period: Vec3,
+        shape: Box<DistanceField>,
+    },
+    /// Twist around the y axis, by `amount` radians per unit length
+    Twist {
+        amount: f64,
+        shape: Box<DistanceField>,
+    },
+}
+
+fn abs(v: Vec3) -> Vec3 {
+    Vec3::new(v.x.abs(), v.y.abs(), v.z.abs())
+}
+
+fn mix(a: f64, b: f64, h: f64) -> f64 {
+    a * (1.0 - h) + b * h
+}
+
+fn repeat(x: f64, period: f64) -> f64 {
+    if period > 0.0 {
+        x - period * (x / period).round()
+    } else {
+        x
+    }
+}
+
+impl DistanceField {
+    pub fn distance(&self, p: Point3) -> f64 {
+        match self {
+            Self::Sphere { center, radius } => (p - *center).length() - radius,
+            Self::Box {
+                center,
+                half_extents,
+            } => {
+                let q = abs(p - *center) - *half_extents;
+                q.max(Vec3::ZEROS).length() + q.min_max_coords().1.min(0.0)
+            }
+            Self::RoundBox {
+                center,
+                half_extents,
+                radius,
+            } => {
+                let q = abs(p - *center) - *half_extents + *radius;
+                q.max(Vec3::ZEROS).length() + q.min_max_coords().1.min(0.0) - radius
+            }
+            Self::Torus {
+                center,
+                major_radius,
+                minor_radius,
+            } => {
+                let p = p - *center;
+                let ring = (p.x * p.x + p.z * p.z).sqrt() - major_radius;
+                (ring * ring + p.y * p.y).sqrt() - minor_radius
+            }
+            Self::Capsule { a, b, radius } => {
+                let pa = p - *a;
+                let ba = *b - *a;
+                let h = (pa.dot(ba) / ba.length_squared()).clamp(0.0, 1.0);
+                (pa - ba * h).length() - radius
+            }
+            Self::Mandelbulb {
+                center,
+                scale,
+                power,
+                iterations,
+            } => {
+                let c = (p - *center) / *scale;
+                let mut z = c;
+                let mut dr = 1.0;
+                let mut r = z.length();
+                for _ in 0..*iterations {
+                    // the direction of `z` is undefined at the origin, which
+                    // is inside the set
+                    if !(1e-12..=2.0).contains(&r) {
+                        break;
+                    }
+                    let theta = (z.z / r).acos() * power;
+                    let phi = z.y.atan2(z.x) * power;
+                    dr = r.powf(power - 1.0) * power * dr + 1.0;
+                    z = Vec3::new(
+                        theta.sin() * phi.cos(),
+                        theta.sin() * phi.sin(),
+                        theta.cos(),
+                    ) * r.powf(*power)
+                        + c;
+                    r = z.length();
+                }
+                if r < 1e-12_f64 {
+                    0.0
+                } else {
+                    0.5 * r.ln() * r / dr * scale
+                }
+            }
+            Self::Union {
+                left,
+                right,
+                smoothness,
+            } => {
+                let (d1, d2) = (left.distance(p), right.distance(p));
+                if *smoothness > 0.0 {
+                    let h = (0.5 + 0.5 * (d2 - d1) / smoothness).clamp(0.0, 1.0);
+                    mix(d2, d1, h) - smoothness * h * (1.0 - h)
+                } else {
+                    d1.min(d2)
+                }
+            }
+            Self::Subtraction {
+                left,
+                right,
+                smoothness,
+            } => {
+                let (d1, d2) = (left.distance(p), right.distance(p));
+                if *smoothness > 0.0 {
+                    let h = (0.5 - 0.5 * (d1 + d2) / smoothness).clamp(0.0, 1.0);
+                    mix(d1, -d2, h) + smoothness * h * (1.0 - h)
+                } else {
+                    d1.max(-d2)
+                }
+            }
+            Self::Repetition { period, shape } => shape.distance(Vec3::new(
+                repeat(p.x, period.x),
+                repeat(p.y, period.y),
+                repeat(p.z, period.z),
+            )),
+            Self::Twist { amount, shape } => {
+                let (sin, cos) = (amount * p.y).sin_cos();
+                let q = Vec3::new(cos * p.x - sin * p.z, p.y, sin * p.x + cos * p.z);
+                // the twist stretches space, so the distance must be scaled down
+                // by the local Lipschitz constant to keep the sphere tracing safe
+                let lipschitz = (1.0 + (amount * (p.x * p.x + p.z * p.z).sqrt()).powi(2)).sqrt();
+                shape.distance(q) / lipschitz
+            }
+        }
+    }
+
+    /// Gradient of the distance field, computed with the tetrahedron technique
+    pub fn normal(&self, p: Point3) -> Vec3 {
+        let k = [
+            Vec3::new(1.0, -1.0, -1.0),
+            Vec3::new(-1.0, -1.0, 1.0),
+            Vec3::new(-1.0, 1.0, -1.0),
+            Vec3::new(1.0, 1.0, 1.0),
+        ];
+        k.iter()
+            .fold(Vec3::ZEROS, |acc, k| {
+                acc + *k * self.distance(p + *k * NORMAL_EPSILON)
+            })
+            .unit_vector()
+    }
+}
+
+/// An object defined by a signed distance function, rendered by sphere tracing.
+/// The bounding box must be given by the user, and clips the shape.
+#[derive(Debug, Clone)]
+pub struct Sdf<'a> {
+    pub shape: DistanceField,
+    pub bounds: BoundingBox,
+    pub material: &'a (dyn Material + 'a),
+}
+
+impl<'a> Sdf<'a> {
+    pub fn new(
+        shape: DistanceField,
+        bounds: BoundingBox,
+        material: &'a (dyn Material + 'a),
+    ) -> Self {
+        Self {
+            shape,
+            bounds,
+            material,
+        }
+    }
+}
+
+impl<'a> Hittable for Sdf<'a> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, _: &mut FastRng) -> Hit {
+        let (start, end) = self.bounds.intersect(ray, t_min, t_max)?;
+        let inv_length = 1.0 / ray.direction.length();
+
+        // Trace on the side of the surface the ray starts from, so that rays
+        // leaving the surface or starting inside the shape are handled as well.
+        let mut time = start;
+        let first = self.shape.distance(ray.at(time));
+        let side = if first.abs() > HIT_DISTANCE {
+            first.signum()
+        } else {
+            self.shape.normal(ray.at(time)).dot(ray.direction).signum()
+        };
+
+        for step in 0..MAX_STEPS {
+            let point = ray.at(time);
+            let distance = side * self.shape.distance(point);
+            if distance < HIT_DISTANCE && step > 0 {
+                return Some(HitRecord::new(
+                    point,
+                    self.shape.normal(point),
+                    time,
+                    (0.0, 0.0),
+                    ray,
+                    self.material,
+                ));
+            }
+            time += distance.max(HIT_DISTANCE) * inv_length;
+            if time > end {
+                break;
+            }
+        }
+        None
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        self.bounds.clone()
+    }
+}
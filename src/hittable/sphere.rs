@@ -2,7 +2,7 @@ use crate::bounding_boxes::BoundingBox;
 use crate::hittable::{Hit, HitRecord, Hittable};
 use crate::materials::Material;
 use crate::ray::Ray;
-use crate::vec3::Point3;
+use crate::vec3::{Point3, Vec3};
 use crate::FastRng;
 
 #[derive(Debug, Clone)]
@@ -20,6 +20,16 @@ impl<'a> Sphere<'a> {
             material,
         }
     }
+
+    /// Spherical coordinates of a point on the unit sphere, mapped to [0, 1]
+    pub fn uv(point: Vec3) -> (f64, f64) {
+        let theta = (-point.y).acos();
+        let phi = (-point.z).atan2(point.x) + std::f64::consts::PI;
+        (
+            phi * 0.5 * std::f64::consts::FRAC_1_PI,
+            theta * std::f64::consts::FRAC_1_PI,
+        )
+    }
 }
 
 impl<'a> Hittable for Sphere<'a> {
@@ -48,6 +58,7 @@ impl<'a> Hittable for Sphere<'a> {
             point,
             outward_normal,
             root,
+            Self::uv(outward_normal),
             ray,
             self.material,
         ))
@@ -0,0 +1,253 @@
+use std::f64::consts::{FRAC_1_PI, PI};
+
+use crate::bounding_boxes::BoundingBox;
+use crate::hittable::{Hit, HitRecord, Hittable};
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+use crate::FastRng;
+
+#[derive(Debug, Clone)]
+pub struct Torus<'a> {
+    pub center: Point3,
+    pub axis: Vec3,
+    pub major_radius: f64,
+    pub minor_radius: f64,
+    pub frame: (Vec3, Vec3),
+    pub material: &'a (dyn Material + 'a),
+}
+
+impl<'a> Torus<'a> {
+    pub fn new(
+        center: Point3,
+        axis: Vec3,
+        major_radius: f64,
+        minor_radius: f64,
+        material: &'a (dyn Material + 'a),
+    ) -> Self {
+        let axis = axis.unit_vector();
+        Self {
+            center,
+            axis,
+            major_radius,
+            minor_radius,
+            frame: axis.orthonormal_basis(),
+            material,
+        }
+    }
+
+    /// Expresses a vector in the local frame of the torus,
+    /// where the z coordinate is along the axis
+    fn to_local(&self, v: Vec3) -> Vec3 {
+        Vec3::new(v.dot(self.frame.0), v.dot(self.frame.1), v.dot(self.axis))
+    }
+}
+
+impl<'a> Hittable for Torus<'a> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, _: &mut FastRng) -> Hit {
+        let scale = ray.direction.length();
+        let direction = self.to_local(ray.direction) / scale;
+        let mut origin = self.to_local(ray.origin - self.center);
+
+        // Move the origin of the ray close to the torus before solving: the
+        // coefficients of the quartic lose a lot of precision when it is far away.
+        let bounding_radius = self.major_radius + self.minor_radius;
+        let half_b = origin.dot(direction);
+        let c = origin.length_squared() - bounding_radius * bounding_radius;
+        let delta = half_b * half_b - c;
+        if delta < 0.0 {
+            return None;
+        }
+        let offset = (-half_b - delta.sqrt()).max(0.0);
+        origin += direction * offset;
+
+        let r2 = self.major_radius * self.major_radius;
+        let e = origin.length_squared() - r2 - self.minor_radius * self.minor_radius;
+        let f = origin.dot(direction);
+        let coefficients = [
+            e * e - 4.0 * r2 * (self.minor_radius * self.minor_radius - origin.z * origin.z),
+            4.0 * f * e + 8.0 * r2 * origin.z * direction.z,
+            2.0 * e + 4.0 * f * f + 4.0 * r2 * direction.z * direction.z,
+            4.0 * f,
+        ];
+
+        let (mut roots, n) = solve_quartic(coefficients);
+        let roots = &mut roots[..n];
+        roots.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+
+        roots
+            .iter()
+            .map(|root| (root + offset) / scale)
+            .find(|&root| root >= t_min && root <= t_max)
+            .map(|root| {
+                let local = origin + direction * (root * scale - offset);
+                let ring = (local.x * local.x + local.y * local.y).sqrt();
+                let to_ring = Vec3::new(local.x, local.y, 0.0) * (self.major_radius / ring);
+                let normal = local - to_ring;
+                let outward_normal =
+                    (self.frame.0 * normal.x + self.frame.1 * normal.y + self.axis * normal.z)
+                        .unit_vector();
+                let u = 0.5 + 0.5 * local.y.atan2(local.x) * FRAC_1_PI;
+                let v = 0.5 + 0.5 * local.z.atan2(ring - self.major_radius) * FRAC_1_PI;
+                HitRecord::new(
+                    ray.at(root),
+                    outward_normal,
+                    root,
+                    (u, v),
+                    ray,
+                    self.material,
+                )
+            })
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        let extent = Vec3::new(
+            (1.0 - self.axis.x * self.axis.x).max(0.0).sqrt(),
+            (1.0 - self.axis.y * self.axis.y).max(0.0).sqrt(),
+            (1.0 - self.axis.z * self.axis.z).max(0.0).sqrt(),
+        ) * self.major_radius
+            + self.minor_radius
+            + 1e-7f64;
+        BoundingBox {
+            minimum: self.center - extent,
+            maximum: self.center + extent,
+        }
+    }
+}
+
+const EPSILON: f64 = 1e-9;
+
+/// Real roots of x² + bx + c
+fn solve_quadratic(b: f64, c: f64) -> ([f64; 2], usize) {
+    let p = 0.5 * b;
+    let delta = p * p - c;
+    if delta.abs() < EPSILON {
+        ([-p, 0.0], 1)
+    } else if delta < 0.0 {
+        ([0.0; 2], 0)
+    } else {
+        let sqrtd = delta.sqrt();
+        ([sqrtd - p, -sqrtd - p], 2)
+    }
+}
+
+/// Real roots of x³ + ax² + bx + c, using Cardano's formulas
+fn solve_cubic(a: f64, b: f64, c: f64) -> ([f64; 3], usize) {
+    // substitute x = y - a/3 to get y³ + 3py + 2q
+    let sq_a = a * a;
+    let p = (-sq_a / 3.0 + b) / 3.0;
+    let q = (2.0 / 27.0 * a * sq_a - a * b / 3.0 + c) / 2.0;
+    let cb_p = p * p * p;
+    let delta = q * q + cb_p;
+
+    let (mut roots, n) = if delta.abs() < EPSILON {
+        if q.abs() < EPSILON {
+            ([0.0; 3], 1)
+        } else {
+            let u = (-q).cbrt();
+            ([2.0 * u, -u, 0.0], 2)
+        }
+    } else if delta < 0.0 {
+        let phi = (-q / (-cb_p).sqrt()).clamp(-1.0, 1.0).acos() / 3.0;
+        let t = 2.0 * (-p).sqrt();
+        (
+            [
+                t * phi.cos(),
+                -t * (phi + PI / 3.0).cos(),
+                -t * (phi - PI / 3.0).cos(),
+            ],
+            3,
+        )
+    } else {
+        let sqrtd = delta.sqrt();
+        ([(sqrtd - q).cbrt() - (sqrtd + q).cbrt(), 0.0, 0.0], 1)
+    };
+
+    for root in &mut roots[..n] {
+        *root -= a / 3.0;
+    }
+    (roots, n)
+}
+
+/// Real roots of x⁴ + c[3]x³ + c[2]x² + c[1]x + c[0], using Ferrari's method.
+/// The roots are refined with a few Newton iterations, since the closed form
+/// is numerically unstable.
+fn solve_quartic(c: [f64; 4]) -> ([f64; 4], usize) {
+    let [d, c1, b, a] = c;
+
+    // substitute x = y - a/4 to get y⁴ + py² + qy + r
+    let sq_a = a * a;
+    let p = -3.0 / 8.0 * sq_a + b;
+    let q = sq_a * a / 8.0 - a * b / 2.0 + c1;
+    let r = -3.0 / 256.0 * sq_a * sq_a + sq_a * b / 16.0 - a * c1 / 4.0 + d;
+
+    let mut roots = [0.0; 4];
+    let mut n = 0;
+
+    if r.abs() < EPSILON {
+        // y(y³ + py + q) = 0
+        let (cubic_roots, m) = solve_cubic(0.0, p, q);
+        roots[..m].copy_from_slice(&cubic_roots[..m]);
+        roots[m] = 0.0;
+        n = m + 1;
+    } else {
+        // take one real root of the resolvent cubic
+        let (cubic_roots, _) = solve_cubic(-p / 2.0, -r, r * p / 2.0 - q * q / 8.0);
+        let z = cubic_roots[0];
+
+        let u = z * z - r;
+        let v = 2.0 * z - p;
+        let u = if u.abs() < EPSILON {
+            0.0
+        } else if u > 0.0 {
+            u.sqrt()
+        } else {
+            return ([0.0; 4], 0);
+        };
+        let v = if v.abs() < EPSILON {
+            0.0
+        } else if v > 0.0 {
+            v.sqrt()
+        } else {
+            return ([0.0; 4], 0);
+        };
+
+        let v = if q < 0.0 { -v } else { v };
+        for (b, c) in [(v, z - u), (-v, z + u)] {
+            let (quadratic_roots, m) = solve_quadratic(b, c);
+            roots[n..n + m].copy_from_slice(&quadratic_roots[..m]);
+            n += m;
+        }
+    }
+
+    for root in &mut roots[..n] {
+        *root -= a / 4.0;
+        for _ in 0..2 {
+            let value = (((*root + a) * *root + b) * *root + c1) * *root + d;
+            let derivative = ((4.0 * *root + 3.0 * a) * *root + 2.0 * b) * *root + c1;
+            if derivative.abs() > EPSILON {
+                *root -= value / derivative;
+            }
+        }
+    }
+    (roots, n)
+}
+
+#[cfg(test)]
+mod test {
+    use super::solve_quartic;
+
+    #[test]
+    fn test_solve_quartic() {
+        // (x - 1)(x - 2)(x + 3)(x - 0.5) = x⁴ - 0.5x³ - 7x² + 9.5x - 3
+        let (mut roots, n) = solve_quartic([-3.0, 9.5, -7.0, -0.5]);
+        assert_eq!(n, 4);
+        roots.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+        for (root, expected) in roots.iter().zip([-3.0, 0.5, 1.0, 2.0]) {
+            assert!((root - expected).abs() < 1e-9);
+        }
+
+        // x⁴ + 1 has no real roots
+        assert_eq!(solve_quartic([1.0, 0.0, 0.0, 0.0]).1, 0);
+    }
+}
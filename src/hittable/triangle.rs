@@ -57,6 +57,7 @@ impl<'a> Hittable for Triangle<'a> {
                     point,
                     normal.unit_vector(),
                     time,
+                    (lambda, mu),
                     ray,
                     self.material,
                 ))
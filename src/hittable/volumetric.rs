@@ -72,6 +72,7 @@ impl<'a> Hittable for Volume<'a> {
             ray.at(time),
             outward_normal,
             time,
+            (0.0, 0.0),
             ray,
             self.material,
         ))
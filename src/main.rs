@@ -1,129 +1,269 @@
+mod batch;
 mod bounding_boxes;
 mod camera;
 mod command_line_interface;
+mod distributed;
 mod fast_random;
+mod film;
 mod hittable;
 mod materials;
 mod ray;
+mod renderer;
+mod sampler;
+mod server;
+mod terminal_preview;
 mod vec3;
+mod watch;
 mod world_loader;
 
-use std::f64::consts::TAU;
+use std::ops::Range;
+use std::path::Path;
+use std::time::{Duration, Instant};
 
-use anyhow::{Context, Result};
+use anyhow::{ensure, Context, Result};
 use clap::Clap;
-use image::{ImageBuffer, Rgb};
-use indicatif::{ParallelProgressIterator, ProgressBar, ProgressFinish, ProgressStyle};
-use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+use indicatif::{ProgressBar, ProgressFinish, ProgressStyle};
 
-use bounding_boxes::BoundingVolumeHierarchy;
+use bounding_boxes::Scene;
 use fast_random::SplitMix64;
 pub use materials::{
     dielectric::Dielectric, emissive::Emissive, lambertian::Lambertian, metal::Metal, ScatterResult,
 };
-use vec3::color::Colour;
+use renderer::{checkpoint::Checkpoint, CancellationToken, Renderer};
+use terminal_preview::TerminalPreview;
 
-use crate::command_line_interface::Opts;
+use crate::command_line_interface::{Opts, RenderOptions};
+use crate::world_loader::Config;
 
 pub type FastRng = SplitMix64;
 
-const M1: u32 = 1597334677u32;
-const M2: u32 = 3812015801u32;
-const M3: u32 = 2741598923u32;
-const M4: f64 = 1.0 / 0xffffffffu32 as f64;
-
-/// Quick hasher function to avoid an expensive call to the rng
-#[inline(always)]
-pub fn hash_fast(mut x: u32, mut y: u32, mut z: u32) -> f64 {
-    x *= M1;
-    y *= M2;
-    z *= M3;
-    let n: u32 = (x ^ y ^ z) * M1;
-    n as f64 * M4
-}
+/// The time between two updates of the preview in the terminal
+const TERMINAL_PREVIEW_INTERVAL: Duration = Duration::from_millis(500);
 
 fn main() -> Result<()> {
     let opts: Opts = Clap::parse();
 
-    if let Opts::Example = opts {
-        command_line_interface::example_config_file();
-        return Ok(());
+    match opts {
+        Opts::Example => {
+            command_line_interface::example_config_file();
+            return Ok(());
+        }
+        Opts::Serve {
+            config,
+            output,
+            bind,
+            samples_per_task,
+        } => {
+            let output = output.unwrap_or_else(|| config.with_extension("png"));
+            return distributed::coordinator::serve(&config, &output, &bind, samples_per_task);
+        }
+        Opts::Worker { address } => return distributed::worker::work(&address),
+        Opts::Http { bind, directory } => return server::serve(&bind, directory),
+        _ => {}
+    }
+
+    let cancellation = CancellationToken::default();
+    cancellation.cancel_on_ctrlc()?;
+    if let Opts::Batch {
+        configs,
+        output,
+        sweeps,
+        overrides,
+    } = &opts
+    {
+        return batch::batch(configs, output.as_deref(), sweeps, overrides, &cancellation);
+    }
+    if let Opts::Render {
+        config,
+        watch: true,
+        ..
+    } = &opts
+    {
+        opts.check()?;
+        return watch::watch(config, &opts, &cancellation);
     }
 
     let mut global_rng = FastRng::new(rand::random::<std::num::NonZeroU64>().into());
-    let (config, out_file, display_tree) = opts.parse(&mut global_rng)?;
+    let (config, options) = opts.parse(&mut global_rng)?;
+    if let Some(time_limit) = options.time_limit {
+        let cancellation = cancellation.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(time_limit);
+            cancellation.cancel();
+        });
+    }
+
+    render(&config, &options, &cancellation, global_rng)
+}
 
+/// Renders a scene until it is complete or `cancellation` is cancelled, and saves it
+fn render(
+    config: &Config,
+    options: &RenderOptions,
+    cancellation: &CancellationToken,
+    rng: FastRng,
+) -> Result<()> {
+    if let Some(frames) = options.frames.clone() {
+        return render_animation(config, options, frames, cancellation, rng);
+    }
     println!(
         "Successfully loaded scene with {} objects and {} materials",
         config.world.objects.len(),
         config.world.materials.len()
     );
 
-    let image_height = config.image.height;
-    let image_width = image_height * config.aspect_ratio();
-    let samples_per_pixel = config.image.samples_per_pixel;
-    let max_depth = config.image.max_depth;
-    let background_color = (&config.world.background_color).into();
-
-    let camera = config.camera();
     let materials = config.materials();
     let hittables = config.world(&materials)?;
-    let world = BoundingVolumeHierarchy::build(&hittables)
-        .with_context(|| "Error building the BVH tree")?;
+    let world = Scene::build(&hittables).with_context(|| "Error building the BVH tree")?;
 
     let (depth, nodes) = world.depth_and_num_nodes();
     println!(
-        "Successfully built BVH tree with {} nodes, depth: {}",
-        nodes, depth
+        "Successfully built BVH tree with {} nodes, depth: {} ({} unbounded objects)",
+        nodes,
+        depth,
+        world.num_unbounded()
     );
-    if display_tree {
+    if options.tree {
         println!("{}", world);
     }
 
-    let total_pixels = image_width as u64 * image_height as u64;
-    let pb = ProgressBar::new(total_pixels).with_style(
+    render_scene(config, &world, options, cancellation, rng)
+}
+
+/// Renders frames of the animation of a scene, to numbered images
+fn render_animation(
+    config: &Config,
+    options: &RenderOptions,
+    frames: Range<u32>,
+    cancellation: &CancellationToken,
+    rng: FastRng,
+) -> Result<()> {
+    ensure!(
+        config.animation.is_some(),
+        "The config has no animation to render frames of."
+    );
+    let numbered = |file: &Path, frame: u32| {
+        let extension = file.extension().unwrap_or_default().to_string_lossy();
+        let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+        file.with_file_name(format!("{}_{:04}.{}", stem, frame, extension))
+    };
+    for frame in frames {
+        if cancellation.is_cancelled() {
+            break;
+        }
+        println!("\nRendering frame {}", frame);
+        let options = RenderOptions {
+            output: numbered(&options.output, frame),
+            spp_map: options.spp_map.as_deref().map(|file| numbered(file, frame)),
+            frames: None,
+            ..options.clone()
+        };
+        // the same random numbers for each frame, so that the noise flickers less
+        render(
+            &config.at_frame(frame as f64)?,
+            &options,
+            cancellation,
+            rng.clone(),
+        )?;
+    }
+    Ok(())
+}
+
+/// Renders a scene whose world is already built, until it is complete or
+/// `cancellation` is cancelled, and saves it
+fn render_scene(
+    config: &Config,
+    world: &Scene,
+    options: &RenderOptions,
+    cancellation: &CancellationToken,
+    rng: FastRng,
+) -> Result<()> {
+    let mut renderer = Renderer::new(config, world, rng)?;
+    if let Some(resume) = &options.resume {
+        let checkpoint = Checkpoint::load(resume)
+            .with_context(|| format!("Could not load the checkpoint `{}`", resume.display()))?;
+        renderer.resume(&checkpoint)?;
+        println!(
+            "Resuming from `{}` ({:.1} samples per pixel on average)",
+            resume.display(),
+            renderer.average_samples_per_pixel()
+        );
+    }
+
+    let samples_per_pixel = config.image.samples_per_pixel;
+    let pb = ProgressBar::new(samples_per_pixel as u64).with_style(
         ProgressStyle::default_bar()
-            .template("[{elapsed_precise}] {bar:50} {percent}%")
+            .template("[{elapsed_precise}] {bar:50} {pos}/{len} spp")
             .on_finish(ProgressFinish::AndLeave),
     );
-    pb.set_draw_delta(total_pixels / 100);
-
-    let buffer: Vec<u8> = (0..image_height * image_width)
-        .into_par_iter()
-        .progress_with(pb.clone())
-        .flat_map(|index| {
-            let j = image_height - 1 - index / image_width;
-            let i = index % image_width;
-
-            let rng: &mut FastRng = global_rng.as_mut();
-
-            (0..samples_per_pixel)
-                .map(|k| {
-                    let u = (i as f64 + hash_fast(i, j, k)) / (image_width - 1) as f64;
-                    let v = (j as f64 + hash_fast(i, k, j)) / (image_height - 1) as f64;
-
-                    camera
-                        .get_ray(u, v, hash_fast(j, i, k), TAU * hash_fast(j, k, i))
-                        .colour(&world, rng, max_depth, background_color)
-                })
-                .fold(Colour::BLACK, |a, b| a + b)
-                .as_bytes(samples_per_pixel)
-        })
-        .collect();
-
-    let img: ImageBuffer<Rgb<u8>, Vec<u8>> =
-        ImageBuffer::from_vec(image_width, image_height, buffer)
-            .ok_or_else(|| anyhow::anyhow!("Could not create image buffer: size mismatch"))?;
-
-    pb.println(format!(
-        "Scene rendered in {} seconds.\nSaving as {}...",
+    pb.set_position(renderer.passes() as u64);
+    let mut terminal_preview = options
+        .terminal_preview
+        .and_then(|protocol| TerminalPreview::new(protocol, renderer.width, renderer.height));
+
+    // the first preview is written after the first pass
+    let mut last_preview: Option<Instant> = None;
+    let mut last_checkpoint = Instant::now();
+    let mut last_terminal_preview = Instant::now();
+    while !renderer.is_complete() && !cancellation.is_cancelled() {
+        renderer.render_pass(cancellation);
+        pb.set_position(renderer.passes() as u64);
+        if let Some(preview) = &mut terminal_preview {
+            if last_terminal_preview.elapsed() >= TERMINAL_PREVIEW_INTERVAL {
+                preview.draw(&renderer.to_image(), &pb);
+                last_terminal_preview = Instant::now();
+            }
+        }
+        if let Some(interval) = options.preview_interval {
+            if last_preview.is_none_or(|last| last.elapsed() >= interval) {
+                renderer.to_image().save(&options.output)?;
+                last_preview = Some(Instant::now());
+            }
+        }
+        if let Some(checkpoint) = &options.checkpoint {
+            if last_checkpoint.elapsed() >= options.checkpoint_interval {
+                renderer.checkpoint().save(checkpoint)?;
+                last_checkpoint = Instant::now();
+            }
+        }
+    }
+    if let Some(preview) = &mut terminal_preview {
+        preview.draw(&renderer.to_image(), &pb);
+    }
+    if cancellation.is_cancelled() {
+        pb.abandon();
+        println!("Rendering stopped early.");
+    } else {
+        pb.finish();
+    }
+
+    println!(
+        "Scene rendered in {} seconds ({:.1} samples per pixel on average).\nSaving as {}...",
         pb.elapsed().as_secs(),
-        out_file.display(),
-    ));
+        renderer.average_samples_per_pixel(),
+        options.output.display(),
+    );
 
-    img.save(out_file)?;
+    renderer.to_image().save(&options.output)?;
+    if let Some(checkpoint) = &options.checkpoint {
+        renderer
+            .checkpoint()
+            .save(checkpoint)
+            .with_context(|| format!("Could not save the checkpoint `{}`", checkpoint.display()))?;
+    }
+    if let Some(spp_map) = &options.spp_map {
+        renderer
+            .sample_count_image()
+            .save(spp_map)
+            .with_context(|| {
+                format!(
+                    "Could not save the sample count image `{}`",
+                    spp_map.display()
+                )
+            })?;
+    }
 
-    pb.println("Successfully saved image.");
+    println!("Successfully saved image.");
 
     Ok(())
 }
@@ -1,10 +1,8 @@
-use rand::Rng;
-
 use super::{Material, ScatterResult};
 use crate::hittable::HitRecord;
 use crate::ray::{Ray, ScatteredRay};
+use crate::sampler::SampleStream;
 use crate::vec3::color::Colour;
-use crate::FastRng;
 
 #[derive(Debug, Clone)]
 pub struct Dielectric {
@@ -13,7 +11,12 @@ pub struct Dielectric {
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, ray: &Ray, hit_record: &HitRecord, rng: &mut FastRng) -> ScatterResult {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut SampleStream,
+    ) -> ScatterResult {
         let refraction_ratio = if hit_record.front_face {
             1.0 / self.refraction_index
         } else {
@@ -23,13 +26,14 @@ impl Material for Dielectric {
         let cos_theta = unit_direction.dot(-hit_record.normal).min(1.0);
         let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
 
-        let refracted_direction =
-            if refraction_ratio * sin_theta > 1.0 || self.reflectance(cos_theta) > rng.gen() {
-                unit_direction.reflect(hit_record.normal)
-            } else {
-                unit_direction.refract(hit_record.normal, refraction_ratio)
-            };
-        let new_ray = Ray::new(hit_record.point, refracted_direction);
+        let refracted_direction = if refraction_ratio * sin_theta > 1.0
+            || self.reflectance(cos_theta) > sampler.get_1d()
+        {
+            unit_direction.reflect(hit_record.normal)
+        } else {
+            unit_direction.refract(hit_record.normal, refraction_ratio)
+        };
+        let new_ray = Ray::new(hit_record.point, refracted_direction, ray.time);
         ScatterResult::Ray(ScatteredRay::new(new_ray, self.attenuation))
     }
 }
@@ -1,8 +1,8 @@
 use super::{Material, ScatterResult};
 use crate::hittable::HitRecord;
 use crate::ray::Ray;
+use crate::sampler::SampleStream;
 use crate::vec3::color::Colour;
-use crate::FastRng;
 
 #[derive(Debug, Clone)]
 pub struct Emissive {
@@ -17,7 +17,7 @@ impl Emissive {
 }
 
 impl Material for Emissive {
-    fn scatter(&self, _: &Ray, _: &HitRecord, _: &mut FastRng) -> ScatterResult {
+    fn scatter(&self, _: &Ray, _: &HitRecord, _: &mut SampleStream) -> ScatterResult {
         ScatterResult::Emissive(self.color * self.intensity)
     }
 }
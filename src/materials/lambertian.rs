@@ -1,9 +1,9 @@
 use super::{Material, ScatterResult};
 use crate::hittable::HitRecord;
 use crate::ray::{Ray, ScatteredRay};
+use crate::sampler::SampleStream;
 use crate::vec3::color::Colour;
 use crate::vec3::Vec3;
-use crate::FastRng;
 
 #[derive(Clone, Debug)]
 pub struct Lambertian {
@@ -11,12 +11,18 @@ pub struct Lambertian {
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, _: &Ray, hit_record: &HitRecord, rng: &mut FastRng) -> ScatterResult {
-        let mut scatter_direction = hit_record.normal + Vec3::random_in_unit_sphere(rng);
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut SampleStream,
+    ) -> ScatterResult {
+        let mut scatter_direction = hit_record.normal
+            + Vec3::in_unit_sphere_from_sample(sampler.get_2d(), sampler.get_1d());
         if scatter_direction.near_zero() {
             scatter_direction = hit_record.normal;
         }
-        let new_ray = Ray::new(hit_record.point, scatter_direction);
+        let new_ray = Ray::new(hit_record.point, scatter_direction, ray.time);
         ScatterResult::Ray(ScatteredRay::new(new_ray, self.albedo))
     }
 }
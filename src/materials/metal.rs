@@ -1,9 +1,9 @@
 use super::{Material, ScatterResult};
 use crate::hittable::HitRecord;
 use crate::ray::{Ray, ScatteredRay};
+use crate::sampler::SampleStream;
 use crate::vec3::color::Colour;
 use crate::vec3::Vec3;
-use crate::FastRng;
 
 #[derive(Clone, Debug)]
 pub struct Metal {
@@ -12,11 +12,19 @@ pub struct Metal {
 }
 
 impl Material for Metal {
-    fn scatter(&self, ray: &Ray, hit_record: &HitRecord, rng: &mut FastRng) -> ScatterResult {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut SampleStream,
+    ) -> ScatterResult {
         let reflected = ray.direction.reflect(hit_record.normal);
         let new_ray = Ray::new(
             hit_record.point,
-            reflected + Vec3::random_in_unit_sphere(rng) * self.fuzziness,
+            reflected
+                + Vec3::in_unit_sphere_from_sample(sampler.get_2d(), sampler.get_1d())
+                    * self.fuzziness,
+            ray.time,
         );
         if reflected.dot(hit_record.normal) > 0.0 && !new_ray.direction.near_zero() {
             ScatterResult::Ray(ScatteredRay::new(new_ray, self.albedo))
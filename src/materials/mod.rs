@@ -9,8 +9,8 @@ use core::fmt::Debug;
 
 use crate::hittable::HitRecord;
 use crate::ray::{Ray, ScatteredRay};
+use crate::sampler::SampleStream;
 use crate::vec3::color::Colour;
-use crate::FastRng;
 
 #[derive(Debug, Clone)]
 pub enum ScatterResult {
@@ -20,5 +20,10 @@ pub enum ScatterResult {
 }
 
 pub trait Material: Debug + Send + Sync {
-    fn scatter(&self, ray: &Ray, hit_record: &HitRecord, rng: &mut FastRng) -> ScatterResult;
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut SampleStream,
+    ) -> ScatterResult;
 }
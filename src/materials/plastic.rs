@@ -1,11 +1,9 @@
-use rand::Rng;
-
 use super::{Material, ScatterResult};
 use crate::hittable::HitRecord;
 use crate::ray::{Ray, ScatteredRay};
+use crate::sampler::SampleStream;
 use crate::vec3::color::Colour;
 use crate::vec3::Vec3;
-use crate::FastRng;
 
 #[derive(Clone, Debug)]
 pub struct Plastic {
@@ -14,23 +12,29 @@ pub struct Plastic {
 }
 
 impl Material for Plastic {
-    fn scatter(&self, ray: &Ray, hit_record: &HitRecord, rng: &mut FastRng) -> ScatterResult {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut SampleStream,
+    ) -> ScatterResult {
         let unit_direction = ray.direction.unit_vector();
         let cos_theta = unit_direction.dot(-hit_record.normal).min(1.0);
 
         let reflected = unit_direction.reflect(hit_record.normal);
 
-        let new_direction = if self.reflectance(cos_theta) > rng.gen() {
-            reflected + Vec3::random_in_unit_sphere(rng) * self.roughness
+        let offset = Vec3::in_unit_sphere_from_sample(sampler.get_2d(), sampler.get_1d());
+        let new_direction = if self.reflectance(cos_theta) > sampler.get_1d() {
+            reflected + offset * self.roughness
         } else {
-            let mut scatter_direction = hit_record.normal + Vec3::random_in_unit_sphere(rng);
+            let mut scatter_direction = hit_record.normal + offset;
             if scatter_direction.near_zero() {
                 scatter_direction = hit_record.normal;
             }
             scatter_direction
         };
 
-        let new_ray = Ray::new(hit_record.point, new_direction);
+        let new_ray = Ray::new(hit_record.point, new_direction, ray.time);
         ScatterResult::Ray(ScatteredRay::new(new_ray, self.albedo))
     }
 }
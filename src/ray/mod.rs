@@ -1,5 +1,6 @@
 use crate::hittable::Hittable;
 use crate::materials::ScatterResult;
+use crate::sampler::SampleStream;
 use crate::vec3::color::Colour;
 use crate::vec3::{Point3, Vec3};
 use crate::FastRng;
@@ -8,11 +9,17 @@ use crate::FastRng;
 pub struct Ray {
     pub origin: Point3,
     pub direction: Vec3,
+    /// The instant at which the ray is cast, within the camera shutter interval
+    pub time: f64,
 }
 
 impl Ray {
-    pub const fn new(origin: Point3, direction: Vec3) -> Self {
-        Self { origin, direction }
+    pub const fn new(origin: Point3, direction: Vec3, time: f64) -> Self {
+        Self {
+            origin,
+            direction,
+            time,
+        }
     }
 
     pub fn at(&self, t: f64) -> Point3 {
@@ -23,6 +30,7 @@ impl Ray {
         self,
         world: &H,
         rng: &mut FastRng,
+        sampler: &mut SampleStream,
         max_depth: u32,
         background_color: Colour,
     ) -> Colour {
@@ -30,7 +38,8 @@ impl Ray {
         let mut i = 0;
         let mut attenuation = Colour::WHITE;
         while let Some(hit_record) = world.hit(&ray, 0.001, f64::INFINITY, rng) {
-            match hit_record.material.scatter(&ray, &hit_record, rng) {
+            sampler.start_bounce(i);
+            match hit_record.material.scatter(&ray, &hit_record, sampler) {
                 ScatterResult::Ray(scattered_ray) => {
                     attenuation *= scattered_ray.attenuation;
                     ray = scattered_ray.ray;
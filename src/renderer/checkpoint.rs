@@ -0,0 +1,175 @@
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use anyhow::{ensure, Context, Result};
+
+use crate::film::statistics::PixelStatistics;
+use crate::vec3::color::Colour;
+
+const MAGIC: &[u8; 8] = b"RTCKPT01";
+/// The size in bytes of the header, and of each pixel after it
+const HEADER_SIZE: u64 = 32;
+const PIXEL_SIZE: u64 = 52;
+
+/// The raw state of a render, enough to resume it: the accumulated samples of
+/// the film, the sample statistics of each pixel and the state of the random
+/// number generator.
+///
+/// The file format is a small header followed by the pixels, all in little endian.
+#[derive(Clone, Debug)]
+pub struct Checkpoint {
+    /// Identifies the scene the checkpoint was made for, see `Config::scene_hash`
+    pub scene_hash: u64,
+    pub width: u32,
+    pub height: u32,
+    pub rng_state: u64,
+    /// The weighted sum of the samples and the total weight of each pixel
+    pub pixels: Vec<(Colour, f64)>,
+    pub statistics: Vec<PixelStatistics>,
+}
+
+impl Checkpoint {
+    /// Saves the checkpoint next to `path` first, then moves it over, so that
+    /// an interrupted save does not lose the previous checkpoint
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let temporary = path.with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&temporary)?);
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&self.scene_hash.to_le_bytes())?;
+        writer.write_all(&self.width.to_le_bytes())?;
+        writer.write_all(&self.height.to_le_bytes())?;
+        writer.write_all(&self.rng_state.to_le_bytes())?;
+        for ((colour, weight), statistics) in self.pixels.iter().zip(&self.statistics) {
+            for value in [colour.r, colour.g, colour.b, *weight] {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+            writer.write_all(&statistics.count.to_le_bytes())?;
+            writer.write_all(&statistics.mean.to_le_bytes())?;
+            writer.write_all(&statistics.m2.to_le_bytes())?;
+        }
+        writer.into_inner()?.sync_all()?;
+
+        fs::rename(&temporary, path)?;
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path)?;
+        let length = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        ensure!(&magic == MAGIC, "Not a checkpoint file.");
+
+        let scene_hash = read_u64(&mut reader)?;
+        let width = read_u32(&mut reader)?;
+        let height = read_u32(&mut reader)?;
+        let rng_state = read_u64(&mut reader)?;
+
+        // the size of the file is checked before allocating the pixels, which
+        // a corrupted header could make too many to fit in memory
+        let size = (width as u64)
+            .checked_mul(height as u64)
+            .filter(|size| {
+                size.checked_mul(PIXEL_SIZE)
+                    .and_then(|pixels| pixels.checked_add(HEADER_SIZE))
+                    == Some(length)
+            })
+            .with_context(|| {
+                format!(
+                    "The checkpoint file is {} bytes long, which does not match its {}x{} pixels.",
+                    length, width, height
+                )
+            })? as usize;
+        let mut pixels = Vec::with_capacity(size);
+        let mut statistics = Vec::with_capacity(size);
+        for _ in 0..size {
+            let colour = Colour::new(
+                read_f64(&mut reader)?,
+                read_f64(&mut reader)?,
+                read_f64(&mut reader)?,
+            );
+            pixels.push((colour, read_f64(&mut reader)?));
+            statistics.push(PixelStatistics {
+                count: read_u32(&mut reader)?,
+                mean: read_f64(&mut reader)?,
+                m2: read_f64(&mut reader)?,
+            });
+        }
+
+        Ok(Self {
+            scene_hash,
+            width,
+            height,
+            rng_state,
+            pixels,
+            statistics,
+        })
+    }
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32> {
+    let mut bytes = [0; 4];
+    reader
+        .read_exact(&mut bytes)
+        .with_context(|| "Truncated checkpoint file")?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> Result<u64> {
+    let mut bytes = [0; 8];
+    reader
+        .read_exact(&mut bytes)
+        .with_context(|| "Truncated checkpoint file")?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_f64(reader: &mut impl Read) -> Result<f64> {
+    read_u64(reader).map(f64::from_bits)
+}
+
+#[cfg(test)]
+mod test {
+    use super::Checkpoint;
+    use crate::film::statistics::PixelStatistics;
+    use crate::vec3::color::Colour;
+
+    #[test]
+    fn test_save_and_load() {
+        let checkpoint = Checkpoint {
+            scene_hash: 0x0123456789abcdef,
+            width: 2,
+            height: 1,
+            rng_state: 42,
+            pixels: vec![(Colour::new(0.1, 0.2, 0.3), 4.0), (Colour::BLACK, 0.0)],
+            statistics: vec![
+                PixelStatistics {
+                    count: 4,
+                    mean: 0.2,
+                    m2: 0.5,
+                },
+                PixelStatistics::default(),
+            ],
+        };
+        let path = std::env::temp_dir().join("test_save_and_load.checkpoint");
+        checkpoint.save(&path).unwrap();
+        let loaded = Checkpoint::load(&path).unwrap();
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes.pop();
+        std::fs::write(&path, bytes).unwrap();
+        let truncated = Checkpoint::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.scene_hash, checkpoint.scene_hash);
+        assert_eq!((loaded.width, loaded.height), (2, 1));
+        assert_eq!(loaded.rng_state, 42);
+        assert_eq!(loaded.pixels, checkpoint.pixels);
+        assert_eq!(loaded.statistics[0].count, 4);
+        assert_eq!(loaded.statistics[0].m2, 0.5);
+        assert!(truncated.is_err());
+    }
+}
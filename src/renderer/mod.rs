@@ -0,0 +1,217 @@
+pub mod checkpoint;
+pub mod tile;
+pub mod tracer;
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+
+use anyhow::{ensure, Result};
+use image::{ImageBuffer, Luma, Rgb};
+
+use crate::bounding_boxes::Scene;
+use crate::film::{self, statistics::PixelStatistics, Film};
+use crate::world_loader::Config;
+use crate::FastRng;
+use checkpoint::Checkpoint;
+use tile::{Tile, TileQueue};
+use tracer::Tracer;
+
+/// With adaptive sampling, the error of a pixel is checked every `ADAPTIVE_BATCH` samples
+const ADAPTIVE_BATCH: u32 = 8;
+
+/// Stops a render from any thread, or from the outside
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Cancels the token on Ctrl-C. A second Ctrl-C exits the process.
+    pub fn cancel_on_ctrlc(&self) -> Result<()> {
+        let token = self.clone();
+        ctrlc::set_handler(move || {
+            if token.0.swap(true, Ordering::Relaxed) {
+                std::process::exit(130);
+            }
+        })?;
+        Ok(())
+    }
+}
+
+/// Renders a scene progressively, by passes of one sample in each pixel
+/// that still needs some. The threads take the tiles of a pass from a queue,
+/// in the order given by the config.
+pub struct Renderer<'a> {
+    pub width: u32,
+    pub height: u32,
+    tracer: Tracer<'a>,
+    film: Film,
+    statistics: Vec<Mutex<PixelStatistics>>,
+    tiles: Vec<Tile>,
+    rng: FastRng,
+    samples_per_pixel: u32,
+    min_samples_per_pixel: u32,
+    adaptive_threshold: f64,
+    scene_hash: u64,
+}
+
+/// Checks the parameters of the image that the renderer can not work with
+pub fn check_image(config: &Config) -> Result<()> {
+    ensure!(
+        config.image.samples_per_pixel > 0,
+        "The number of samples per pixel must be at least 1."
+    );
+    Ok(())
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(config: &Config, world: &'a Scene<'a>, rng: FastRng) -> Result<Self> {
+        check_image(config)?;
+        let tracer = Tracer::new(config, world)?;
+        let (width, height) = (tracer.width, tracer.height);
+        let samples_per_pixel = config.image.samples_per_pixel;
+        let adaptive_threshold = config.image.adaptive_threshold;
+        let min_samples_per_pixel = if adaptive_threshold > 0.0 {
+            config
+                .image
+                .min_samples_per_pixel
+                .clamp(1, samples_per_pixel)
+        } else {
+            samples_per_pixel
+        };
+
+        Ok(Self {
+            width,
+            height,
+            tracer,
+            film: Film::new(width, height, (&config.image.filter).into()),
+            statistics: (0..width * height)
+                .map(|_| Mutex::new(PixelStatistics::default()))
+                .collect(),
+            tiles: tile::tiles(
+                width,
+                height,
+                config.image.tile_size,
+                config.image.tile_order.into(),
+            ),
+            rng,
+            samples_per_pixel,
+            min_samples_per_pixel,
+            adaptive_threshold,
+            scene_hash: config.scene_hash(),
+        })
+    }
+
+    fn needs_sample(&self, statistics: &PixelStatistics) -> bool {
+        statistics.count < self.samples_per_pixel
+            && !(statistics.count >= self.min_samples_per_pixel
+                && statistics.count.is_multiple_of(ADAPTIVE_BATCH)
+                && statistics.relative_error() < self.adaptive_threshold)
+    }
+
+    /// Whether all the pixels have received the samples they need
+    pub fn is_complete(&self) -> bool {
+        !self
+            .statistics
+            .iter()
+            .any(|s| self.needs_sample(&s.lock().unwrap()))
+    }
+
+    /// Takes one more sample in each pixel that needs it. Tiles are not started
+    /// anymore once `cancellation` is cancelled, leaving the pass unfinished.
+    pub fn render_pass(&self, cancellation: &CancellationToken) {
+        let queue = TileQueue::new(&self.tiles);
+        rayon::scope(|scope| {
+            for _ in 0..rayon::current_num_threads() {
+                scope.spawn(|_| {
+                    while let Some(tile) = queue.pop() {
+                        if cancellation.is_cancelled() {
+                            return;
+                        }
+                        self.render_tile(tile);
+                    }
+                });
+            }
+        });
+    }
+
+    fn render_tile(&self, tile: Tile) {
+        let rng = self.rng.as_mut();
+        for (i, j) in tile.pixels() {
+            let mut statistics = self.statistics[(j * self.width + i) as usize]
+                .lock()
+                .unwrap();
+            if self.needs_sample(&statistics) {
+                let colour = self
+                    .tracer
+                    .sample((i, j), statistics.count, &self.film, rng);
+                statistics.add(colour.luminance());
+            }
+        }
+    }
+
+    /// The number of passes done so far, the highest number of samples in a pixel
+    pub fn passes(&self) -> u32 {
+        self.statistics().iter().map(|s| s.count).max().unwrap_or(0)
+    }
+
+    fn statistics(&self) -> Vec<PixelStatistics> {
+        self.statistics.iter().map(|s| *s.lock().unwrap()).collect()
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            scene_hash: self.scene_hash,
+            width: self.width,
+            height: self.height,
+            rng_state: self.rng.state(),
+            pixels: self.film.accumulated(),
+            statistics: self.statistics(),
+        }
+    }
+
+    /// Continues a render from a checkpoint of the same scene
+    pub fn resume(&mut self, checkpoint: &Checkpoint) -> Result<()> {
+        ensure!(
+            checkpoint.scene_hash == self.scene_hash,
+            "The checkpoint was made for a different scene."
+        );
+        ensure!(
+            (checkpoint.width, checkpoint.height) == (self.width, self.height),
+            "The checkpoint is {}x{} while the image is {}x{}.",
+            checkpoint.width,
+            checkpoint.height,
+            self.width,
+            self.height
+        );
+        self.film.restore(&checkpoint.pixels);
+        for (statistics, &saved) in self.statistics.iter_mut().zip(&checkpoint.statistics) {
+            *statistics.get_mut().unwrap() = saved;
+        }
+        self.rng = FastRng::new(checkpoint.rng_state);
+        Ok(())
+    }
+
+    /// The average number of samples taken per pixel
+    pub fn average_samples_per_pixel(&self) -> f64 {
+        let statistics = self.statistics();
+        statistics.iter().map(|s| s.count as f64).sum::<f64>() / statistics.len() as f64
+    }
+
+    pub fn to_image(&self) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+        self.film.to_image()
+    }
+
+    pub fn sample_count_image(&self) -> ImageBuffer<Luma<u8>, Vec<u8>> {
+        let counts: Vec<u32> = self.statistics().iter().map(|s| s.count).collect();
+        film::sample_count_image(self.width, self.height, &counts)
+    }
+}
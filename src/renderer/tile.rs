@@ -0,0 +1,148 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+/// The order in which the tiles of an image are rendered
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TileOrder {
+    /// From the centre of the image outwards, where the subject usually is
+    #[default]
+    CentreOut,
+    /// Along a Hilbert curve, so that consecutive tiles are neighbours
+    Hilbert,
+}
+
+/// A rectangle of pixels, `width` x `height` from its top left corner `(x, y)`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Tile {
+    /// The coordinates of the pixels of the tile, row by row
+    pub fn pixels(self) -> impl Iterator<Item = (u32, u32)> {
+        (self.y..self.y + self.height)
+            .flat_map(move |j| (self.x..self.x + self.width).map(move |i| (i, j)))
+    }
+
+    /// The tile grown by `margin` pixels on each side, within an image of
+    /// `width` x `height` pixels
+    pub fn with_margin(self, margin: u32, width: u32, height: u32) -> Tile {
+        let (x, y) = (self.x.saturating_sub(margin), self.y.saturating_sub(margin));
+        Tile {
+            x,
+            y,
+            width: (self.x + self.width + margin).min(width) - x,
+            height: (self.y + self.height + margin).min(height) - y,
+        }
+    }
+}
+
+/// Splits an image into square tiles of side `size`, smaller on the right and
+/// bottom edges, in the given order
+pub fn tiles(width: u32, height: u32, size: u32, order: TileOrder) -> Vec<Tile> {
+    let size = size.max(1);
+    let (columns, rows) = (width.div_ceil(size), height.div_ceil(size));
+    let mut tiles: Vec<_> = (0..rows)
+        .flat_map(|row| (0..columns).map(move |column| (column, row)))
+        .collect();
+
+    match order {
+        TileOrder::CentreOut => {
+            let centre = (columns as f64 / 2.0, rows as f64 / 2.0);
+            tiles.sort_by(|a, b| {
+                let distance = |&(column, row): &(u32, u32)| {
+                    (column as f64 + 0.5 - centre.0).hypot(row as f64 + 0.5 - centre.1)
+                };
+                distance(a).total_cmp(&distance(b))
+            });
+        }
+        TileOrder::Hilbert => {
+            let side = columns.max(rows).next_power_of_two();
+            tiles.sort_by_key(|&(column, row)| hilbert_index(side, column, row));
+        }
+    }
+
+    tiles
+        .into_iter()
+        .map(|(column, row)| Tile {
+            x: column * size,
+            y: row * size,
+            width: size.min(width - column * size),
+            height: size.min(height - row * size),
+        })
+        .collect()
+}
+
+/// The distance of `(x, y)` along the Hilbert curve filling a square of side `side`,
+/// a power of two
+fn hilbert_index(side: u32, mut x: u32, mut y: u32) -> u64 {
+    let mut index = 0;
+    let mut s = side / 2;
+    while s > 0 {
+        let rx = (x & s > 0) as u32;
+        let ry = (y & s > 0) as u32;
+        index += s as u64 * s as u64 * ((3 * rx) ^ ry) as u64;
+        // rotates the quadrant so that the curve stays continuous
+        if ry == 0 {
+            if rx == 1 {
+                x = side - 1 - x;
+                y = side - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    index
+}
+
+/// The tiles left to render, shared between the threads
+#[derive(Debug)]
+pub struct TileQueue {
+    tiles: Mutex<VecDeque<Tile>>,
+}
+
+impl TileQueue {
+    pub fn new(tiles: &[Tile]) -> Self {
+        Self {
+            tiles: Mutex::new(tiles.iter().copied().collect()),
+        }
+    }
+
+    pub fn pop(&self) -> Option<Tile> {
+        self.tiles.lock().unwrap().pop_front()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{tiles, TileOrder};
+
+    #[test]
+    fn test_tiles_cover_image() {
+        for order in [TileOrder::CentreOut, TileOrder::Hilbert] {
+            let mut covered = vec![0; 37 * 21];
+            for tile in tiles(37, 21, 8, order) {
+                for (i, j) in tile.pixels() {
+                    covered[(j * 37 + i) as usize] += 1;
+                }
+            }
+            assert!(covered.iter().all(|&c| c == 1));
+        }
+    }
+
+    #[test]
+    fn test_hilbert_neighbours() {
+        // consecutive tiles of a Hilbert curve share an edge
+        let tiles = tiles(64, 64, 8, TileOrder::Hilbert);
+        for pair in tiles.windows(2) {
+            let distance = (pair[0].x as i64 - pair[1].x as i64).abs()
+                + (pair[0].y as i64 - pair[1].y as i64).abs();
+            assert_eq!(distance, 8);
+        }
+    }
+}
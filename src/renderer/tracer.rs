@@ -0,0 +1,89 @@
+use std::f64::consts::TAU;
+use std::ops::Range;
+
+use anyhow::Result;
+use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+
+use super::tile::Tile;
+use crate::bounding_boxes::Scene;
+use crate::camera::Camera;
+use crate::film::{filter::Filter, Film};
+use crate::sampler::{SampleStream, Sampler};
+use crate::vec3::color::Colour;
+use crate::world_loader::Config;
+use crate::FastRng;
+
+/// Traces the samples of the pixels of an image, whatever film they are
+/// accumulated into
+pub struct Tracer<'a> {
+    pub width: u32,
+    pub height: u32,
+    world: &'a Scene<'a>,
+    camera: Camera,
+    sampler: Box<dyn Sampler>,
+    max_depth: u32,
+    background_color: Colour,
+}
+
+impl<'a> Tracer<'a> {
+    pub fn new(config: &Config, world: &'a Scene<'a>) -> Result<Self> {
+        let height = config.image.height;
+        Ok(Self {
+            width: height * &config.aspect_ratio(),
+            height,
+            world,
+            camera: config.camera()?,
+            sampler: config.sampler(),
+            max_depth: config.image.max_depth,
+            background_color: (&config.world.background_color).into(),
+        })
+    }
+
+    /// Takes the sample `k` of the pixel `(i, j)` and adds it to `film`
+    pub fn sample(&self, (i, j): (u32, u32), k: u32, film: &Film, rng: &mut FastRng) -> Colour {
+        let mut sample = SampleStream::new(self.sampler.as_ref(), (i, j), k);
+        let (dx, dy) = sample.get_2d();
+        let (x, y) = (i as f64 + dx, j as f64 + dy);
+        let u = x / self.width as f64;
+        let v = 1.0 - y / self.height as f64;
+        let (r, theta) = sample.get_2d();
+        let time = sample.get_1d();
+
+        let colour = self
+            .camera
+            .get_ray(u, v, r, TAU * theta, time)
+            .map(|(ray, filter)| {
+                ray.colour(
+                    self.world,
+                    rng,
+                    &mut sample,
+                    self.max_depth,
+                    self.background_color,
+                ) * filter
+            })
+            .unwrap_or(Colour::BLACK);
+        film.add_sample(x, y, colour);
+        colour
+    }
+
+    /// Takes the samples `samples` of each pixel of `tile`, into a film covering
+    /// the tile and the pixels around it that the filter reaches
+    pub fn render_tile(
+        &self,
+        tile: Tile,
+        samples: Range<u32>,
+        filter: Filter,
+        rng: &FastRng,
+    ) -> Film {
+        let window = tile.with_margin(filter.radius().ceil() as u32, self.width, self.height);
+        let film = Film::new(window.width, window.height, filter).with_origin((window.x, window.y));
+
+        let pixels: Vec<_> = tile.pixels().collect();
+        pixels.into_par_iter().for_each(|pixel| {
+            for k in samples.clone() {
+                self.sample(pixel, k, &film, rng.as_mut());
+            }
+        });
+        film
+    }
+}
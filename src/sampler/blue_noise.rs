@@ -0,0 +1,139 @@
+use super::sobol::Sobol;
+use super::{hash, Sampler, ONE_MINUS_EPSILON};
+
+/// Side of the tiled blue noise mask
+const SIZE: usize = 64;
+/// Standard deviation of the Gaussian kernel of the void and cluster algorithm
+const SIGMA: f64 = 1.5;
+
+/// The same scrambled Sobol sequence for all pixels, shifted in each pixel by the
+/// values of a blue noise mask. At low sample counts, the error is then spread as
+/// blue noise over the image, which looks much less noisy.
+#[derive(Clone, Debug)]
+pub struct BlueNoise {
+    pub sobol: Sobol,
+    /// Values in [0, 1), `SIZE * SIZE` pixels stored row by row
+    pub mask: Vec<f64>,
+}
+
+impl BlueNoise {
+    pub fn new() -> Self {
+        Self {
+            sobol: Sobol::new(),
+            mask: void_and_cluster(),
+        }
+    }
+}
+
+impl Default for BlueNoise {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The energy of a binary pattern, as the sum of Gaussians centered on its points
+#[derive(Clone)]
+struct Energy {
+    kernel: Vec<f64>,
+    energy: Vec<f64>,
+    pattern: Vec<bool>,
+}
+
+impl Energy {
+    fn new() -> Self {
+        let wrap = |d: usize| d.min(SIZE - d) as f64;
+        let kernel = (0..SIZE * SIZE)
+            .map(|p| {
+                let (dx, dy) = (wrap(p % SIZE), wrap(p / SIZE));
+                (-(dx * dx + dy * dy) / (2.0 * SIGMA * SIGMA)).exp()
+            })
+            .collect();
+        Self {
+            kernel,
+            energy: vec![0.0; SIZE * SIZE],
+            pattern: vec![false; SIZE * SIZE],
+        }
+    }
+
+    fn toggle(&mut self, p: usize) {
+        self.pattern[p] = !self.pattern[p];
+        let sign = if self.pattern[p] { 1.0 } else { -1.0 };
+        let (px, py) = (p % SIZE, p / SIZE);
+        for (q, energy) in self.energy.iter_mut().enumerate() {
+            let dx = (q % SIZE + SIZE - px) % SIZE;
+            let dy = (q / SIZE + SIZE - py) % SIZE;
+            *energy += sign * self.kernel[dy * SIZE + dx];
+        }
+    }
+
+    /// The point of the pattern with the highest energy
+    fn tightest_cluster(&self) -> usize {
+        (0..SIZE * SIZE)
+            .filter(|&p| self.pattern[p])
+            .max_by(|&a, &b| self.energy[a].partial_cmp(&self.energy[b]).unwrap())
+            .unwrap()
+    }
+
+    /// The empty pixel with the lowest energy
+    fn largest_void(&self) -> usize {
+        (0..SIZE * SIZE)
+            .filter(|&p| !self.pattern[p])
+            .min_by(|&a, &b| self.energy[a].partial_cmp(&self.energy[b]).unwrap())
+            .unwrap()
+    }
+}
+
+/// Generates a blue noise mask with the void and cluster algorithm of Ulichney
+fn void_and_cluster() -> Vec<f64> {
+    let count = SIZE * SIZE;
+    let mut initial = Energy::new();
+    for i in 0..count / 10 {
+        let p = (hash(&[i as u64]) % count as u64) as usize;
+        if !initial.pattern[p] {
+            initial.toggle(p);
+        }
+    }
+    // spread the initial points evenly, by moving points from clusters to voids
+    loop {
+        let cluster = initial.tightest_cluster();
+        initial.toggle(cluster);
+        let void = initial.largest_void();
+        initial.toggle(void);
+        if void == cluster {
+            break;
+        }
+    }
+    let initial_points = initial.pattern.iter().filter(|&&p| p).count();
+
+    let mut rank = vec![0; count];
+    // the initial points are ranked by removing clusters one by one
+    let mut energy = initial.clone();
+    for r in (0..initial_points).rev() {
+        let cluster = energy.tightest_cluster();
+        energy.toggle(cluster);
+        rank[cluster] = r;
+    }
+    // the other ones by filling voids one by one
+    let mut energy = initial;
+    for r in initial_points..count {
+        let void = energy.largest_void();
+        energy.toggle(void);
+        rank[void] = r;
+    }
+
+    rank.into_iter()
+        .map(|r| (r as f64 + 0.5) / count as f64)
+        .collect()
+}
+
+impl Sampler for BlueNoise {
+    fn sample(&self, pixel: (u32, u32), index: u32, dimension: u32) -> f64 {
+        // each dimension reads the mask with a different offset
+        let offset = hash(&[dimension as u64]);
+        let x = (pixel.0 as usize + (offset % SIZE as u64) as usize) % SIZE;
+        let y = (pixel.1 as usize + ((offset >> 32) % SIZE as u64) as usize) % SIZE;
+        let shift = self.mask[y * SIZE + x];
+        let value = self.sobol.scrambled(0, index, dimension) + shift;
+        (value - value.floor()).min(ONE_MINUS_EPSILON)
+    }
+}
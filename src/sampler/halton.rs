@@ -0,0 +1,64 @@
+use super::independent::Independent;
+use super::{hash, permutation_element, Sampler, ONE_MINUS_EPSILON};
+
+/// Number of dimensions of the Halton sequence, the next ones are independent
+const DIMENSIONS: usize = 256;
+
+/// The Halton sequence, using the nth prime as the base of the nth dimension.
+/// Each pixel scrambles the sequence with its own Owen scrambling.
+#[derive(Clone, Debug)]
+pub struct Halton {
+    pub primes: Vec<u64>,
+}
+
+impl Halton {
+    pub fn new() -> Self {
+        let mut primes = Vec::with_capacity(DIMENSIONS);
+        let mut candidate = 2;
+        while primes.len() < DIMENSIONS {
+            if primes.iter().all(|p| candidate % p != 0) {
+                primes.push(candidate);
+            }
+            candidate += 1;
+        }
+        Self { primes }
+    }
+}
+
+impl Default for Halton {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Radical inverse of `index` in `base`, with Owen scrambling: each digit is
+/// permuted by a random permutation depending on `seed` and on the previous digits
+fn scrambled_radical_inverse(base: u64, mut index: u64, seed: u64) -> f64 {
+    let inverse_base = 1.0 / base as f64;
+    let mut scale = inverse_base;
+    let mut result = 0.0;
+    let mut state = seed;
+    // the trailing zero digits are scrambled as well, up to the precision of an f64
+    while scale > 1e-16 {
+        let digit = index % base;
+        let permuted = permutation_element(digit as u32, base as u32, state as u32);
+        result += permuted as f64 * scale;
+        state = hash(&[state, digit]);
+        index /= base;
+        scale *= inverse_base;
+    }
+    result.min(ONE_MINUS_EPSILON)
+}
+
+impl Sampler for Halton {
+    fn sample(&self, pixel: (u32, u32), index: u32, dimension: u32) -> f64 {
+        match self.primes.get(dimension as usize) {
+            Some(&base) => scrambled_radical_inverse(
+                base,
+                index as u64,
+                hash(&[pixel.0 as u64, pixel.1 as u64, dimension as u64]),
+            ),
+            None => Independent.sample(pixel, index, dimension),
+        }
+    }
+}
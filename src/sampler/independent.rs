@@ -0,0 +1,16 @@
+use super::{hash, to_unit, Sampler};
+
+/// Uniform random samples, with no relation between the samples of a pixel
+#[derive(Clone, Debug)]
+pub struct Independent;
+
+impl Sampler for Independent {
+    fn sample(&self, pixel: (u32, u32), index: u32, dimension: u32) -> f64 {
+        to_unit(hash(&[
+            pixel.0 as u64,
+            pixel.1 as u64,
+            index as u64,
+            dimension as u64,
+        ]))
+    }
+}
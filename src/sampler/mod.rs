@@ -0,0 +1,116 @@
+pub mod blue_noise;
+pub mod halton;
+pub mod independent;
+pub mod sobol;
+pub mod stratified;
+
+use std::fmt::Debug;
+
+/// Dimensions used by the camera: the position in the pixel, the lens and the time
+pub const CAMERA_DIMENSIONS: u32 = 6;
+/// Dimensions reserved for each bounce of a path
+pub const BOUNCE_DIMENSIONS: u32 = 4;
+
+/// The largest f64 below 1
+pub const ONE_MINUS_EPSILON: f64 = 1.0 - f64::EPSILON / 2.0;
+
+/// Generates the samples of a pixel. Each sample is a point of the unit hypercube,
+/// whose coordinates are the dimensions: the camera uses the first ones, and each
+/// bounce of a path uses the next `BOUNCE_DIMENSIONS`.
+///
+/// Samplers are deterministic, so they can be shared between threads.
+pub trait Sampler: Debug + Send + Sync {
+    /// The coordinate `dimension` of the sample `index` of the pixel, in [0, 1)
+    fn sample(&self, pixel: (u32, u32), index: u32, dimension: u32) -> f64;
+}
+
+/// Reads the successive dimensions of a sample
+pub struct SampleStream<'a> {
+    sampler: &'a dyn Sampler,
+    pixel: (u32, u32),
+    index: u32,
+    dimension: u32,
+}
+
+impl<'a> SampleStream<'a> {
+    pub fn new(sampler: &'a dyn Sampler, pixel: (u32, u32), index: u32) -> Self {
+        Self {
+            sampler,
+            pixel,
+            index,
+            dimension: 0,
+        }
+    }
+
+    pub fn get_1d(&mut self) -> f64 {
+        let value = self.sampler.sample(self.pixel, self.index, self.dimension);
+        self.dimension += 1;
+        value
+    }
+
+    /// Returns two dimensions, starting from an even one so that samplers
+    /// stratifying pairs of dimensions get both of a pair
+    pub fn get_2d(&mut self) -> (f64, f64) {
+        self.dimension += self.dimension % 2;
+        (self.get_1d(), self.get_1d())
+    }
+
+    /// Moves to the dimensions of the bounce `depth` of the path
+    pub fn start_bounce(&mut self, depth: u32) {
+        self.dimension = CAMERA_DIMENSIONS + depth * BOUNCE_DIMENSIONS;
+    }
+}
+
+/// Mixes bits of a 64 bit integer, with the finalizer of SplitMix64
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// Hashes a list of integers
+pub fn hash(values: &[u64]) -> u64 {
+    values
+        .iter()
+        .fold(0x9e3779b97f4a7c15, |h, v| mix(h ^ mix(*v)))
+}
+
+/// Returns the element `i` of a random permutation of [0, length), picked by `seed`,
+/// without storing the permutation (Kensler, Correlated Multi-Jittered Sampling)
+pub fn permutation_element(mut i: u32, length: u32, seed: u32) -> u32 {
+    let mut w = length - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    loop {
+        i ^= seed;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= seed >> 16;
+        i ^= (i & w) >> 4;
+        i ^= seed >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= seed >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | seed >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < length {
+            break;
+        }
+    }
+    i.wrapping_add(seed) % length
+}
+
+/// Converts random bits to a uniform float in [0, 1)
+pub fn to_unit(bits: u64) -> f64 {
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -0,0 +1,95 @@
+use super::{hash, Sampler};
+
+/// Owen-scrambled Sobol samples, following Burley, Practical Hash-based Owen
+/// Scrambling. Each pair of dimensions uses the first two dimensions of the
+/// Sobol sequence, shuffled and scrambled with its own seeds.
+#[derive(Clone, Debug)]
+pub struct Sobol {
+    /// The direction numbers of the second dimension of the Sobol sequence
+    pub directions: [u32; 32],
+}
+
+impl Sobol {
+    pub fn new() -> Self {
+        let mut directions = [0; 32];
+        directions[0] = 1 << 31;
+        for k in 1..32 {
+            directions[k] = directions[k - 1] ^ (directions[k - 1] >> 1);
+        }
+        Self { directions }
+    }
+
+    /// The 32 bit fixed point coordinate `dimension` (0 or 1) of the point `index`
+    /// of the Sobol sequence
+    pub fn sobol(&self, index: u32, dimension: u32) -> u32 {
+        if dimension == 0 {
+            return index.reverse_bits();
+        }
+        (0..32)
+            .filter(|k| index & (1 << k) != 0)
+            .fold(0, |acc, k| acc ^ self.directions[k])
+    }
+
+    /// A sample of the scrambled sequence, with scrambling seeds computed from `seed`
+    pub fn scrambled(&self, seed: u64, index: u32, dimension: u32) -> f64 {
+        let pair_seed = hash(&[seed, (dimension / 2) as u64]);
+        let index = nested_uniform_scramble(index, pair_seed as u32);
+        let value = self.sobol(index, dimension % 2);
+        let value = nested_uniform_scramble(value, hash(&[pair_seed, dimension as u64]) as u32);
+        value as f64 / (1u64 << 32) as f64
+    }
+}
+
+impl Default for Sobol {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A hash-based approximation of an Owen scrambling of the bits of `x`, reversed
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x ^= x.wrapping_mul(0x3d20adea);
+    x = x.wrapping_add(seed);
+    x = x.wrapping_mul((seed >> 16) | 1);
+    x ^= x.wrapping_mul(0x05526c56);
+    x ^= x.wrapping_mul(0x53a22864);
+    x
+}
+
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+}
+
+impl Sampler for Sobol {
+    fn sample(&self, pixel: (u32, u32), index: u32, dimension: u32) -> f64 {
+        self.scrambled(hash(&[pixel.0 as u64, pixel.1 as u64]), index, dimension)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Sobol;
+    use crate::sampler::Sampler;
+
+    #[test]
+    fn test_stratification() {
+        // scrambling keeps the first 2^k points of each dimension in distinct
+        // intervals of size 2^-k, and the pairs in distinct cells of a 2^(k/2) grid
+        let sobol = Sobol::new();
+        for dimension in 0..8 {
+            let mut intervals = [false; 16];
+            for index in 0..16 {
+                let value = sobol.sample((3, 5), index, dimension);
+                intervals[(value * 16.0) as usize] = true;
+            }
+            assert!(intervals.iter().all(|&seen| seen));
+        }
+        let mut cells = [false; 16];
+        for index in 0..16 {
+            let x = sobol.sample((3, 5), index, 2);
+            let y = sobol.sample((3, 5), index, 3);
+            cells[(x * 4.0) as usize * 4 + (y * 4.0) as usize] = true;
+        }
+        assert!(cells.iter().all(|&seen| seen));
+    }
+}
@@ -0,0 +1,47 @@
+use super::independent::Independent;
+use super::{hash, permutation_element, to_unit, Sampler};
+
+/// Jittered samples, on a grid of `strata * strata` cells for each pair of
+/// dimensions. The cells are shuffled differently for each pair of dimensions.
+/// Samples beyond the largest square number below the number of samples per
+/// pixel are independent.
+#[derive(Clone, Debug)]
+pub struct Stratified {
+    pub samples_per_pixel: u32,
+    pub strata: u32,
+}
+
+impl Stratified {
+    pub fn new(samples_per_pixel: u32) -> Self {
+        Self {
+            samples_per_pixel: samples_per_pixel.max(1),
+            strata: (samples_per_pixel as f64).sqrt().floor().max(1.0) as u32,
+        }
+    }
+}
+
+impl Sampler for Stratified {
+    fn sample(&self, pixel: (u32, u32), index: u32, dimension: u32) -> f64 {
+        let (round, within_round) = (
+            index / self.samples_per_pixel,
+            index % self.samples_per_pixel,
+        );
+        let seed = hash(&[
+            pixel.0 as u64,
+            pixel.1 as u64,
+            round as u64,
+            (dimension / 2) as u64,
+        ]);
+        let cell = permutation_element(within_round, self.samples_per_pixel, seed as u32);
+        if cell >= self.strata * self.strata {
+            return Independent.sample(pixel, index, dimension);
+        }
+        let stratum = if dimension.is_multiple_of(2) {
+            cell % self.strata
+        } else {
+            cell / self.strata
+        };
+        let jitter = to_unit(hash(&[seed, within_round as u64, dimension as u64]));
+        (stratum as f64 + jitter) / self.strata as f64
+    }
+}
@@ -0,0 +1,344 @@
+//! A small HTTP API to render scenes one at a time:
+//!
+//! - `POST /jobs` with a RON config as the body queues a job, and returns its id
+//! - `GET /jobs` returns the status of all the jobs
+//! - `GET /jobs/<id>` returns the status of a job
+//! - `GET /jobs/<id>/image` returns the image of a job as a PNG, as far as it is rendered
+//! - `DELETE /jobs/<id>` cancels a job, keeping its image if it has started, or
+//!   removes it if it is finished
+//!
+//! Responses other than images are JSON. Only the last `MAX_FINISHED_JOBS`
+//! finished jobs are kept.
+
+use std::any::Any;
+use std::collections::{BTreeMap, VecDeque};
+use std::io::Cursor;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context, Result};
+use image::{ImageBuffer, ImageOutputFormat, Rgb};
+use serde::Serialize;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::bounding_boxes::Scene;
+use crate::renderer::{check_image, CancellationToken, Renderer};
+use crate::world_loader::Config;
+use crate::FastRng;
+
+/// The minimum time between two updates of the image of a job
+const IMAGE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The number of finished jobs kept, with their images, before the oldest ones
+/// are removed
+const MAX_FINISHED_JOBS: usize = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum State {
+    Queued,
+    Rendering,
+    Done,
+    Cancelled,
+    Failed,
+}
+
+impl State {
+    fn is_finished(self) -> bool {
+        matches!(self, State::Done | State::Cancelled | State::Failed)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Status {
+    id: u64,
+    state: State,
+    /// The fraction of the samples per pixel taken, between 0 and 1
+    progress: f64,
+    width: u32,
+    height: u32,
+    /// The average number of samples taken per pixel
+    samples_per_pixel: f64,
+    error: Option<String>,
+}
+
+struct Job {
+    config: Config,
+    cancellation: CancellationToken,
+    status: Mutex<Status>,
+    image: Mutex<Option<ImageBuffer<Rgb<u8>, Vec<u8>>>>,
+}
+
+/// The jobs, and the queue of the ones waiting to be rendered
+#[derive(Default)]
+struct Jobs {
+    last_id: AtomicU64,
+    jobs: Mutex<BTreeMap<u64, Arc<Job>>>,
+    queue: Mutex<VecDeque<Arc<Job>>>,
+    queued: Condvar,
+}
+
+impl Jobs {
+    fn add(&self, config: Config) -> u64 {
+        let mut jobs = self.jobs.lock().unwrap();
+        let id = self.last_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let height = config.image.height;
+        let job = Arc::new(Job {
+            status: Mutex::new(Status {
+                id,
+                state: State::Queued,
+                progress: 0.0,
+                width: height * &config.aspect_ratio(),
+                height,
+                samples_per_pixel: 0.0,
+                error: None,
+            }),
+            config,
+            cancellation: CancellationToken::default(),
+            image: Mutex::new(None),
+        });
+        jobs.insert(id, job.clone());
+
+        let finished: Vec<_> = jobs
+            .iter()
+            .filter(|(_, job)| job.status.lock().unwrap().state.is_finished())
+            .map(|(&id, _)| id)
+            .collect();
+        for id in &finished[..finished.len().saturating_sub(MAX_FINISHED_JOBS)] {
+            jobs.remove(id);
+        }
+
+        self.queue.lock().unwrap().push_back(job);
+        self.queued.notify_one();
+        id
+    }
+
+    fn get(&self, id: u64) -> Option<Arc<Job>> {
+        self.jobs.lock().unwrap().get(&id).cloned()
+    }
+
+    /// Waits for the next job in the queue
+    fn next(&self) -> Arc<Job> {
+        let mut queue = self.queue.lock().unwrap();
+        loop {
+            match queue.pop_front() {
+                Some(job) => return job,
+                None => queue = self.queued.wait(queue).unwrap(),
+            }
+        }
+    }
+}
+
+impl Job {
+    fn render(&self) -> Result<()> {
+        let materials = self.config.materials();
+        let hittables = self.config.world(&materials)?;
+        let world = Scene::build(&hittables).with_context(|| "Error building the BVH tree")?;
+        let rng = FastRng::new(rand::random::<std::num::NonZeroU64>().into());
+        let renderer = Renderer::new(&self.config, &world, rng)?;
+        let samples_per_pixel = self.config.image.samples_per_pixel;
+
+        let mut last_image = Instant::now();
+        while !renderer.is_complete() && !self.cancellation.is_cancelled() {
+            renderer.render_pass(&self.cancellation);
+            {
+                let mut status = self.status.lock().unwrap();
+                status.progress = renderer.passes() as f64 / samples_per_pixel as f64;
+                status.samples_per_pixel = renderer.average_samples_per_pixel();
+            }
+            if last_image.elapsed() >= IMAGE_INTERVAL {
+                *self.image.lock().unwrap() = Some(renderer.to_image());
+                last_image = Instant::now();
+            }
+        }
+        *self.image.lock().unwrap() = Some(renderer.to_image());
+        Ok(())
+    }
+}
+
+/// Renders the jobs of the queue one after the other
+fn render_jobs(jobs: &Jobs) {
+    loop {
+        let job = jobs.next();
+        {
+            let mut status = job.status.lock().unwrap();
+            if status.state != State::Queued {
+                continue;
+            }
+            status.state = State::Rendering;
+        }
+        // a panic fails the job rather than stopping the rendering of the queue
+        let result =
+            panic::catch_unwind(AssertUnwindSafe(|| job.render())).unwrap_or_else(|panic| {
+                Err(anyhow!("The render panicked: {}", panic_message(&*panic)))
+            });
+        let mut status = job.status.lock().unwrap();
+        status.state = match result {
+            Err(error) => {
+                status.error = Some(format!("{:#}", error));
+                State::Failed
+            }
+            Ok(()) if job.cancellation.is_cancelled() => State::Cancelled,
+            Ok(()) => {
+                status.progress = 1.0;
+                State::Done
+            }
+        };
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    match (panic.downcast_ref::<&str>(), panic.downcast_ref::<String>()) {
+        (Some(message), _) => message,
+        (_, Some(message)) => message,
+        _ => "unknown error",
+    }
+}
+
+/// Checks that a config can be rendered, as far as can be known before
+/// building its world
+fn validate(config: &Config) -> Result<()> {
+    check_image(config)?;
+    config.camera()?;
+    Ok(())
+}
+
+/// Listens for HTTP requests on `address`. Relative paths in the scenes are
+/// resolved from `directory`.
+pub fn serve(address: &str, directory: PathBuf) -> Result<()> {
+    let server =
+        Server::http(address).map_err(|e| anyhow!("Could not listen on {}: {}", address, e))?;
+    let jobs = Arc::new(Jobs::default());
+    {
+        let jobs = jobs.clone();
+        thread::spawn(move || render_jobs(&jobs));
+    }
+    println!("Listening on http://{}", address);
+
+    for mut request in server.incoming_requests() {
+        let response = handle(&mut request, &jobs, &directory);
+        if let Err(error) = request.respond(response) {
+            println!("Error sending a response: {}", error);
+        }
+    }
+    Ok(())
+}
+
+type HttpResponse = Response<Cursor<Vec<u8>>>;
+
+fn json<T: Serialize>(status: u16, value: &T) -> HttpResponse {
+    Response::from_data(serde_json::to_vec(value).unwrap_or_default())
+        .with_status_code(status)
+        .with_header(Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap())
+}
+
+fn error(status: u16, message: &str) -> HttpResponse {
+    json(status, &serde_json::json!({ "error": message }))
+}
+
+fn handle(request: &mut Request, jobs: &Jobs, directory: &Path) -> HttpResponse {
+    let url = request.url().to_owned();
+    let segments: Vec<_> = url.trim_matches('/').split('/').collect();
+    let job = segments
+        .get(1)
+        .and_then(|id| id.parse().ok())
+        .and_then(|id| jobs.get(id));
+
+    match (request.method(), segments.as_slice()) {
+        (Method::Post, ["jobs"]) => {
+            let mut body = String::new();
+            if let Err(e) = request.as_reader().read_to_string(&mut body) {
+                return error(400, &format!("Could not read the request: {}", e));
+            }
+            let config = Config::parse_str(&body, directory.to_path_buf())
+                .with_context(|| "Error parsing the config")
+                .and_then(|config| validate(&config).map(|_| config));
+            match config {
+                Ok(config) => json(201, &serde_json::json!({ "id": jobs.add(config) })),
+                Err(e) => error(400, &format!("{:#}", e)),
+            }
+        }
+        (Method::Get, ["jobs"]) => {
+            let statuses: Vec<_> = jobs
+                .jobs
+                .lock()
+                .unwrap()
+                .values()
+                .map(|job| job.status.lock().unwrap().clone())
+                .collect();
+            json(200, &statuses)
+        }
+        (_, ["jobs", _, ..]) if job.is_none() => error(404, "No such job"),
+        (Method::Get, ["jobs", _]) => json(200, &*job.unwrap().status.lock().unwrap()),
+        (Method::Get, ["jobs", _, "image"]) => {
+            let image = job.unwrap().image.lock().unwrap().clone();
+            let image = match image {
+                Some(image) => image,
+                None => return error(404, "The job has no image yet"),
+            };
+            let mut png = Vec::new();
+            match image::DynamicImage::ImageRgb8(image).write_to(&mut png, ImageOutputFormat::Png) {
+                Ok(()) => Response::from_data(png).with_header(
+                    Header::from_bytes(&b"Content-Type"[..], &b"image/png"[..]).unwrap(),
+                ),
+                Err(e) => error(500, &format!("Could not encode the image: {}", e)),
+            }
+        }
+        (Method::Delete, ["jobs", _]) => {
+            let job = job.unwrap();
+            let (status, finished) = {
+                let mut status = job.status.lock().unwrap();
+                let finished = status.state.is_finished();
+                if status.state == State::Queued {
+                    status.state = State::Cancelled;
+                }
+                (status.clone(), finished)
+            };
+            job.cancellation.cancel();
+            if finished {
+                jobs.jobs.lock().unwrap().remove(&status.id);
+            }
+            json(200, &status)
+        }
+        _ => error(404, "Not found"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use tiny_http::{Method, StatusCode, TestRequest};
+
+    use super::{handle, Jobs};
+    use crate::command_line_interface::EXAMPLE_FILE;
+
+    #[test]
+    fn test_handle() {
+        let jobs = Jobs::default();
+        let request = |method, path: &str, body| {
+            let request = TestRequest::new()
+                .with_method(method)
+                .with_path(path)
+                .with_body(body);
+            handle(&mut request.into(), &jobs, Path::new(".")).status_code()
+        };
+
+        assert_eq!(request(Method::Get, "/jobs/1", ""), StatusCode(404));
+        assert_eq!(
+            request(Method::Post, "/jobs", EXAMPLE_FILE),
+            StatusCode(201)
+        );
+        assert_eq!(request(Method::Post, "/jobs", "Config("), StatusCode(400));
+        assert_eq!(request(Method::Delete, "/jobs/1", ""), StatusCode(200));
+        assert_eq!(request(Method::Get, "/jobs/1", ""), StatusCode(200));
+        // the job is cancelled, so deleting it again removes it
+        assert_eq!(request(Method::Delete, "/jobs/1", ""), StatusCode(200));
+        assert_eq!(request(Method::Get, "/jobs/1", ""), StatusCode(404));
+        assert_eq!(request(Method::Get, "/jobs/2", ""), StatusCode(404));
+    }
+}
@@ -0,0 +1,265 @@
+//! A live preview of the render in the terminal, drawn above the progress bar,
+//! with truecolor half-block characters or as a sixel image.
+
+use std::fmt::Write;
+use std::str::FromStr;
+
+use console::Term;
+use image::{imageops, imageops::FilterType, ImageBuffer, Rgb};
+use indicatif::ProgressBar;
+
+/// How the preview is drawn
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Protocol {
+    /// Sixel if the terminal seems to support it, half-blocks otherwise
+    Auto,
+    /// Each character shows two pixels: the top one as the foreground colour of
+    /// '▀', the bottom one as the background colour
+    Blocks,
+    Sixel,
+}
+
+impl FromStr for Protocol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "blocks" => Ok(Self::Blocks),
+            "sixel" => Ok(Self::Sixel),
+            _ => Err(format!(
+                "unknown preview `{}`, expecting auto, blocks or sixel",
+                s
+            )),
+        }
+    }
+}
+
+/// Terminals known to support sixel, as there is no reliable way to ask
+const SIXEL_TERMINALS: &[&str] = &["foot", "mlterm", "yaft", "contour", "WezTerm"];
+
+fn terminal_supports_sixel() -> bool {
+    ["TERM", "TERM_PROGRAM"].iter().any(|variable| {
+        std::env::var(variable)
+            .map(|value| {
+                value.contains("sixel") || SIXEL_TERMINALS.iter().any(|t| value.starts_with(t))
+            })
+            .unwrap_or(false)
+    })
+}
+
+/// The size of a character cell in pixels, when the terminal reports it
+#[cfg(unix)]
+fn cell_size() -> Option<(u32, u32)> {
+    // SAFETY: TIOCGWINSZ only writes a winsize
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    let result = unsafe { libc::ioctl(libc::STDERR_FILENO, libc::TIOCGWINSZ, &mut size) };
+    if result != 0 || size.ws_xpixel == 0 || size.ws_ypixel == 0 {
+        return None;
+    }
+    Some((
+        (size.ws_xpixel / size.ws_col.max(1)) as u32,
+        (size.ws_ypixel / size.ws_row.max(1)) as u32,
+    ))
+}
+
+#[cfg(not(unix))]
+fn cell_size() -> Option<(u32, u32)> {
+    None
+}
+
+#[derive(Debug)]
+pub struct TerminalPreview {
+    /// With sixel, the size of a character cell in pixels
+    sixel: Option<(u32, u32)>,
+    /// The size of the preview in characters
+    columns: u32,
+    rows: u32,
+    drawn: bool,
+}
+
+impl TerminalPreview {
+    /// Fits a preview of an image in the terminal, leaving room for the
+    /// progress bar. Returns `None` if the progress bar is not drawn to a terminal.
+    pub fn new(protocol: Protocol, width: u32, height: u32) -> Option<Self> {
+        let term = Term::stderr();
+        if !term.is_term() {
+            return None;
+        }
+        let (term_rows, term_columns) = term.size();
+        let (max_columns, max_rows) = (term_columns as u32, (term_rows as u32).saturating_sub(3));
+
+        let sixel = match protocol {
+            Protocol::Blocks => None,
+            Protocol::Sixel => cell_size(),
+            Protocol::Auto => cell_size().filter(|_| terminal_supports_sixel()),
+        };
+        // the size of a character in preview pixels
+        let (cell_width, cell_height) = sixel.unwrap_or((1, 2));
+        let aspect = width as f64 / height as f64;
+        let mut rows = max_rows;
+        let mut columns = (rows as f64 * cell_height as f64 * aspect / cell_width as f64) as u32;
+        if columns > max_columns {
+            columns = max_columns;
+            rows = (columns as f64 * cell_width as f64 / aspect / cell_height as f64).ceil() as u32;
+        }
+        if rows == 0 || columns == 0 {
+            return None;
+        }
+        Some(Self {
+            sixel,
+            columns,
+            rows,
+            drawn: false,
+        })
+    }
+
+    /// Draws the image over the previous preview, above the progress bar
+    pub fn draw(&mut self, image: &ImageBuffer<Rgb<u8>, Vec<u8>>, pb: &ProgressBar) {
+        let mut text = String::new();
+        if self.drawn {
+            // moves the cursor back to the top of the previous preview
+            write!(text, "\x1b[{}A\r", self.rows).unwrap();
+        }
+        match self.sixel {
+            Some((cell_width, cell_height)) => {
+                let preview = imageops::resize(
+                    image,
+                    self.columns * cell_width,
+                    self.rows * cell_height,
+                    FilterType::Triangle,
+                );
+                // the cursor ends on the last row of text the image covers
+                text.push_str(&sixel(&preview));
+            }
+            None => {
+                let preview =
+                    imageops::resize(image, self.columns, self.rows * 2, FilterType::Triangle);
+                text.push_str(&half_blocks(&preview));
+            }
+        }
+        pb.println(text);
+        self.drawn = true;
+    }
+}
+
+/// Encodes an image of an even height as lines of half-block characters, two
+/// rows of pixels per line
+fn half_blocks(image: &ImageBuffer<Rgb<u8>, Vec<u8>>) -> String {
+    let mut text = String::new();
+    for row in 0..image.height() / 2 {
+        for column in 0..image.width() {
+            let top = image.get_pixel(column, 2 * row).0;
+            let bottom = image.get_pixel(column, 2 * row + 1).0;
+            write!(
+                text,
+                "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m▀",
+                top[0], top[1], top[2], bottom[0], bottom[1], bottom[2]
+            )
+            .unwrap();
+        }
+        text.push_str("\x1b[0m\n");
+    }
+    text.pop();
+    text
+}
+
+/// The index of the closest colour of the 6x6x6 cube of the sixel palette
+fn palette_index(pixel: &Rgb<u8>) -> usize {
+    let level = |c: u8| (c as u32 * 5 + 127) / 255;
+    let [r, g, b] = pixel.0;
+    (level(r) * 36 + level(g) * 6 + level(b)) as usize
+}
+
+/// Encodes an image as sixel, with the colours reduced to a 6x6x6 cube
+fn sixel(image: &ImageBuffer<Rgb<u8>, Vec<u8>>) -> String {
+    // zero bits leave the pixels as they are, so that colours can overlay
+    let mut text = format!("\x1bP0;1q\"1;1;{};{}", image.width(), image.height());
+    for i in 0..216 {
+        let percent = |level: usize| level * 100 / 5;
+        write!(
+            text,
+            "#{};2;{};{};{}",
+            i,
+            percent(i / 36),
+            percent(i / 6 % 6),
+            percent(i % 6)
+        )
+        .unwrap();
+    }
+
+    for band in (0..image.height()).step_by(6) {
+        let rows = (image.height() - band).min(6);
+        // the bits of the 6 pixels of each column of the band, for each colour
+        let mut colours = vec![Vec::new(); 216];
+        for x in 0..image.width() {
+            for dy in 0..rows {
+                let colour = &mut colours[palette_index(image.get_pixel(x, band + dy))];
+                if colour.is_empty() {
+                    colour.resize(image.width() as usize, 0u8);
+                }
+                colour[x as usize] |= 1 << dy;
+            }
+        }
+        for (i, bits) in colours.iter().enumerate().filter(|(_, b)| !b.is_empty()) {
+            write!(text, "#{}", i).unwrap();
+            // run length encoding
+            let mut x = 0;
+            while x < bits.len() {
+                let run = bits[x..].iter().take_while(|&&b| b == bits[x]).count();
+                let character = (63 + bits[x]) as char;
+                if run > 3 {
+                    write!(text, "!{}{}", run, character).unwrap();
+                } else {
+                    text.extend(std::iter::repeat_n(character, run));
+                }
+                x += run;
+            }
+            text.push('$');
+        }
+        text.push('-');
+    }
+    text.push_str("\x1b\\");
+    text
+}
+
+#[cfg(test)]
+mod test {
+    use image::{ImageBuffer, Rgb};
+
+    use super::{half_blocks, palette_index, sixel};
+
+    #[test]
+    fn test_half_blocks() {
+        let image = ImageBuffer::from_fn(2, 4, |x, y| Rgb([x as u8, y as u8, 7]));
+        let text = half_blocks(&image);
+        let lines: Vec<_> = text.split('\n').collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("\x1b[38;2;0;0;7m\x1b[48;2;0;1;7m▀\x1b[38;2;1;0;7m"));
+        assert!(lines[1].starts_with("\x1b[38;2;0;2;7m\x1b[48;2;0;3;7m▀"));
+        assert!(lines.iter().all(|line| line.ends_with("▀\x1b[0m")));
+        assert_eq!(text.matches('▀').count(), 4);
+    }
+
+    #[test]
+    fn test_palette_index() {
+        assert_eq!(palette_index(&Rgb([0, 0, 0])), 0);
+        assert_eq!(palette_index(&Rgb([255, 255, 255])), 215);
+        assert_eq!(palette_index(&Rgb([255, 0, 0])), 180);
+        assert_eq!(palette_index(&Rgb([0, 0, 255])), 5);
+        assert_eq!(palette_index(&Rgb([128, 128, 128])), 3 * 43);
+    }
+
+    #[test]
+    fn test_sixel() {
+        // 7 rows make a full band of 6 and a band of 1, and the runs of 8
+        // identical columns are run length encoded
+        let image = ImageBuffer::from_pixel(8, 7, Rgb([255, 0, 0]));
+        let text = sixel(&image);
+        assert!(text.starts_with("\x1bP0;1q\"1;1;8;7#0;2;0;0;0"));
+        assert!(text.ends_with("#180!8~$-#180!8@$-\x1b\\"));
+
+        let image = ImageBuffer::from_fn(2, 1, |x, _| Rgb([0, 0, 255 * x as u8]));
+        assert!(sixel(&image).ends_with("#0@?$#5?@$-\x1b\\"));
+    }
+}
@@ -62,6 +62,12 @@ impl Colour {
         ]
     }
 
+    /// Relative luminance, with the Rec. 709 primaries
+    #[inline]
+    pub fn luminance(self) -> f64 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
     #[inline]
     pub const fn components(self) -> (f64, f64, f64) {
         (self.r, self.g, self.b)
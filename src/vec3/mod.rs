@@ -1,6 +1,8 @@
 pub mod color;
 mod tests;
 
+use std::f64::consts::TAU;
+
 use rand::Rng;
 
 pub type Point3 = Vec3;
@@ -99,11 +101,36 @@ impl Vec3 {
         }
     }
 
+    /// Maps a uniform sample of [0, 1)² to a uniformly distributed unit vector
+    #[inline]
+    pub fn unit_vector_from_sample((u, v): (f64, f64)) -> Self {
+        let z = 1.0 - 2.0 * u;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let (sin, cos) = (TAU * v).sin_cos();
+        Self::new(r * cos, r * sin, z)
+    }
+
+    /// Maps a uniform sample of [0, 1)³ to a point uniformly distributed in the unit ball
+    #[inline]
+    pub fn in_unit_sphere_from_sample(direction: (f64, f64), radius: f64) -> Self {
+        Self::unit_vector_from_sample(direction) * radius.cbrt()
+    }
+
     #[inline]
     pub fn random_unit_vector<T: Rng>(rng: &mut T) -> Self {
         (Self::random(rng) * 2.0 - 1.0).unit_vector()
     }
 
+    /// Returns two unit vectors `(u, v)` such that `(u, v, self)` is a direct
+    /// orthonormal basis. `self` must be a unit vector.
+    #[inline]
+    pub fn orthonormal_basis(self) -> (Self, Self) {
+        let a = if self.x.abs() > 0.9 { Self::Y } else { Self::X };
+        let u = self.cross(a).unit_vector();
+        let v = self.cross(u);
+        (u, v)
+    }
+
     #[inline]
     pub fn near_zero(self) -> bool {
         let epsilon = 1e-8_f64;
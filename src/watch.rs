@@ -0,0 +1,72 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::Result;
+
+use crate::command_line_interface::Opts;
+use crate::renderer::CancellationToken;
+use crate::FastRng;
+
+/// How often the files are checked for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+fn modification_times(files: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    files
+        .iter()
+        .map(|file| fs::metadata(file).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+/// Waits until one of the files changes, or `cancellation` is cancelled, and
+/// then cancels `restart`. `restart` is also cancelled once `time_limit` has
+/// passed, to stop the render, but the files are still watched.
+fn wait_for_change(
+    files: &[PathBuf],
+    time_limit: Option<Duration>,
+    restart: &CancellationToken,
+    cancellation: &CancellationToken,
+) {
+    let start = Instant::now();
+    let times = modification_times(files);
+    while !cancellation.is_cancelled() && modification_times(files) == times {
+        if time_limit.is_some_and(|limit| start.elapsed() >= limit) {
+            restart.cancel();
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    restart.cancel();
+}
+
+/// Renders the config file again each time it or one of the files it
+/// references changes, until `cancellation` is cancelled. Errors are reported
+/// without stopping, and the time limit applies to each render.
+pub fn watch(config_file: &Path, opts: &Opts, cancellation: &CancellationToken) -> Result<()> {
+    let mut rng = FastRng::new(rand::random::<std::num::NonZeroU64>().into());
+    loop {
+        let mut files = vec![config_file.to_path_buf()];
+        let restart = CancellationToken::default();
+        match opts.clone().parse(&mut rng) {
+            Ok((config, options)) => {
+                files.extend(config.referenced_files());
+                thread::scope(|scope| {
+                    scope.spawn(|| {
+                        wait_for_change(&files, options.time_limit, &restart, cancellation)
+                    });
+                    if let Err(error) = crate::render(&config, &options, &restart, rng.clone()) {
+                        println!("Error: {:#}", error);
+                    }
+                });
+            }
+            Err(error) => {
+                println!("Error: {:#}", error);
+                wait_for_change(&files, None, &restart, cancellation);
+            }
+        }
+        if cancellation.is_cancelled() {
+            return Ok(());
+        }
+        println!("\nThe scene changed, rendering it again.");
+    }
+}
@@ -0,0 +1,149 @@
+use anyhow::{ensure, Context, Result};
+use serde_json::{Number, Value};
+
+use super::structures::{Interpolation, Key, Track};
+use super::Config;
+
+impl Config {
+    /// The config at a frame of its animation, or the config itself if it has none
+    pub fn at_frame(&self, frame: f64) -> Result<Config> {
+        let animation = match &self.animation {
+            Some(animation) => animation,
+            None => return Ok(self.clone()),
+        };
+        let values = animation
+            .tracks
+            .iter()
+            .map(|(path, track)| {
+                let value = track
+                    .value(frame)
+                    .with_context(|| format!("Error in the animation of `{}`", path))?;
+                Ok((path.as_str(), value))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.with_values(values)
+            .with_context(|| format!("Error animating frame {}", frame))
+    }
+}
+
+impl Track {
+    fn value(&self, frame: f64) -> Result<Value> {
+        let mut keys: Vec<&Key> = self.keys.iter().collect();
+        keys.sort_by(|a, b| a.frame.total_cmp(&b.frame));
+        ensure!(!keys.is_empty(), "A track needs at least one key.");
+        ensure!(
+            keys.windows(2).all(|pair| pair[0].frame < pair[1].frame),
+            "Two keys are at the same frame."
+        );
+
+        // the keys around the frame, p1 and p2, and their neighbours, p0 and p3
+        let next = keys.iter().position(|key| key.frame > frame);
+        let (i1, i2) = match next {
+            Some(0) => return Ok(keys[0].value.clone()),
+            None => return Ok(keys[keys.len() - 1].value.clone()),
+            Some(i) => (i - 1, i),
+        };
+        let (p0, p1, p2) = (keys[i1.saturating_sub(1)], keys[i1], keys[i2]);
+        let p3 = keys[(i2 + 1).min(keys.len() - 1)];
+        let t = (frame - p1.frame) / (p2.frame - p1.frame);
+
+        let weights = match self.interpolation {
+            Interpolation::Linear => [0.0, 1.0 - t, t, 0.0],
+            Interpolation::Bezier => {
+                let s = t * t * (3.0 - 2.0 * t);
+                [0.0, 1.0 - s, s, 0.0]
+            }
+            Interpolation::CatmullRom => {
+                // the Hermite curve whose tangents at p1 and p2 are the slopes
+                // between their neighbours, scaled for unevenly spaced keys
+                let a = (p2.frame - p1.frame) / (p2.frame - p0.frame);
+                let b = (p2.frame - p1.frame) / (p3.frame - p1.frame);
+                let (t2, t3) = (t * t, t * t * t);
+                let h00 = 2.0 * t3 - 3.0 * t2 + 1.0;
+                let h10 = t3 - 2.0 * t2 + t;
+                let h01 = -2.0 * t3 + 3.0 * t2;
+                let h11 = t3 - t2;
+                [-h10 * a, h00 - h11 * b, h01 + h10 * a, h11 * b]
+            }
+        };
+
+        let values = [&p0.value, &p1.value, &p2.value, &p3.value];
+        Ok(interpolate(values, weights).unwrap_or_else(|| p1.value.clone()))
+    }
+}
+
+/// The weighted sum of numbers, or of tuples of numbers element by element.
+/// Integers stay integers. `None` if the values can not be interpolated.
+fn interpolate(values: [&Value; 4], weights: [f64; 4]) -> Option<Value> {
+    match values {
+        [Value::Number(_), ..] => {
+            let mut sum = 0.0;
+            for (value, weight) in values.iter().zip(weights) {
+                sum += value.as_f64()? * weight;
+            }
+            Some(Value::Number(if values.iter().all(|v| v.is_i64()) {
+                Number::from(sum.round() as i64)
+            } else {
+                Number::from_f64(sum)?
+            }))
+        }
+        [Value::Array(first), ..] => {
+            let arrays = values
+                .iter()
+                .map(|value| value.as_array().filter(|a| a.len() == first.len()))
+                .collect::<Option<Vec<_>>>()?;
+            (0..first.len())
+                .map(|i| {
+                    interpolate(
+                        [&arrays[0][i], &arrays[1][i], &arrays[2][i], &arrays[3][i]],
+                        weights,
+                    )
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(Value::Array)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::world_loader::structures::{Interpolation, Key, Track};
+
+    #[test]
+    fn test_track_value() {
+        let mut track = Track {
+            interpolation: Interpolation::Linear,
+            keys: vec![
+                Key {
+                    frame: 0.0,
+                    value: json!([0.0, 2.0, 0.0]),
+                },
+                Key {
+                    frame: 10.0,
+                    value: json!([1.0, 2.0, 4.0]),
+                },
+                Key {
+                    frame: 30.0,
+                    value: json!([3.0, 2.0, 0.0]),
+                },
+            ],
+        };
+        assert_eq!(track.value(-5.0).unwrap(), json!([0.0, 2.0, 0.0]));
+        assert_eq!(track.value(5.0).unwrap(), json!([0.5, 2.0, 2.0]));
+        assert_eq!(track.value(40.0).unwrap(), json!([3.0, 2.0, 0.0]));
+
+        // splines pass through the keys
+        for interpolation in [Interpolation::CatmullRom, Interpolation::Bezier] {
+            track.interpolation = interpolation;
+            assert_eq!(track.value(10.0).unwrap(), json!([1.0, 2.0, 4.0]));
+        }
+        // on a straight line, Catmull-Rom stays on it
+        track.interpolation = Interpolation::CatmullRom;
+        let value = track.value(20.0).unwrap();
+        assert!((value[0].as_f64().unwrap() - 2.0).abs() < 1e-9);
+        assert!((value[1].as_f64().unwrap() - 2.0).abs() < 1e-9);
+    }
+}
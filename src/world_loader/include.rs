@@ -0,0 +1,269 @@
+//! Config files including other config files, such as material libraries,
+//! groups of objects or camera presets. The included files are merged in order,
+//! and then the including file on top of them:
+//!
+//! - `image`, `camera`, `world.background_color` and `animation` are replaced
+//!   by the ones of the later files
+//! - materials are merged, a material replacing the one of the same name
+//! - objects are added, those of the included files first
+//!
+//! A file included several times, such as a material library included by two
+//! camera presets, is merged only where it is first included. Relative paths
+//! in an included file are relative to that file.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use ron::de::from_str;
+
+use super::structures::{ApertureShape, Camera, Config, ConfigFile, Object, World};
+
+impl Config {
+    /// Parses a config file, and the files it includes. `file` is the path of
+    /// the config file, if it has one, and `directory` the directory its
+    /// relative paths are resolved from.
+    pub(super) fn load(text: &str, directory: PathBuf, file: Option<&Path>) -> Result<Self> {
+        let config_file = from_str::<ConfigFile>(text).map_err(|e| anyhow!(e))?;
+        let mut stack: Vec<_> = file
+            .into_iter()
+            .filter_map(|f| f.canonicalize().ok())
+            .collect();
+        let mut included_files = Vec::new();
+        let merged = config_file.resolve(
+            Path::new(""),
+            &directory,
+            &mut stack,
+            &mut HashSet::new(),
+            &mut included_files,
+        )?;
+
+        let missing = |name| {
+            anyhow!(
+                "`{}` is missing from the config and the files it includes.",
+                name
+            )
+        };
+        Ok(Config {
+            image: merged.image.ok_or_else(|| missing("image"))?,
+            camera: merged.camera.ok_or_else(|| missing("camera"))?,
+            world: World {
+                background_color: merged
+                    .world
+                    .background_color
+                    .ok_or_else(|| missing("world.background_color"))?,
+                materials: merged.world.materials,
+                objects: merged.world.objects,
+            },
+            animation: merged.animation,
+            directory,
+            included_files,
+        })
+    }
+}
+
+impl ConfigFile {
+    /// Merges the files the file includes, recursively, and the file on top of
+    /// them. `directory` is the directory of the file relative to `base`, the one
+    /// of the scene, `stack` the files being included, to detect cycles, and
+    /// `merged_files` the files already included, to merge them only once.
+    fn resolve(
+        mut self,
+        directory: &Path,
+        base: &Path,
+        stack: &mut Vec<PathBuf>,
+        merged_files: &mut HashSet<PathBuf>,
+        included_files: &mut Vec<PathBuf>,
+    ) -> Result<Self> {
+        let mut merged = ConfigFile::default();
+        for include in std::mem::take(&mut self.include) {
+            let relative = directory.join(&include);
+            let path = base.join(&relative);
+            let canonical = path.canonicalize().with_context(|| {
+                format!("Could not open the included file `{}`", path.display())
+            })?;
+            if let Some(start) = stack.iter().position(|file| *file == canonical) {
+                let cycle: Vec<_> = stack[start..]
+                    .iter()
+                    .chain([&canonical])
+                    .map(|file| format!("`{}`", file.display()))
+                    .collect();
+                bail!("The included files form a cycle: {}", cycle.join(" -> "));
+            }
+            if !merged_files.insert(canonical.clone()) {
+                continue;
+            }
+
+            let text = fs::read_to_string(&path).with_context(|| {
+                format!("Could not read the included file `{}`", path.display())
+            })?;
+            let included = from_str::<ConfigFile>(&text)
+                .map_err(|e| anyhow!(e))
+                .with_context(|| format!("Error parsing the included file `{}`", path.display()))?;
+            included_files.push(path);
+
+            stack.push(canonical);
+            let included = included.resolve(
+                relative.parent().unwrap_or(directory),
+                base,
+                stack,
+                merged_files,
+                included_files,
+            )?;
+            stack.pop();
+            merged.merge(included);
+        }
+
+        self.rebase(directory);
+        merged.merge(self);
+        Ok(merged)
+    }
+
+    /// Adds the values of `other` to the ones of the file, following the
+    /// override rules of includes
+    fn merge(&mut self, other: ConfigFile) {
+        self.image = other.image.or(self.image.take());
+        self.camera = other.camera.or(self.camera.take());
+        self.animation = other.animation.or(self.animation.take());
+        self.world.background_color = other
+            .world
+            .background_color
+            .or(self.world.background_color.take());
+        self.world.materials.extend(other.world.materials);
+        self.world.objects.extend(other.world.objects);
+    }
+
+    /// Makes the relative paths of the file relative to the directory of the
+    /// scene instead, `directory` being the one of the file relative to it
+    fn rebase(&mut self, directory: &Path) {
+        if let Some(camera) = &mut self.camera {
+            camera.rebase(directory);
+        }
+        for object in &mut self.world.objects {
+            object.rebase(directory);
+        }
+    }
+}
+
+impl Camera {
+    fn rebase(&mut self, directory: &Path) {
+        match self {
+            Camera::ThinLens {
+                aperture_shape: ApertureShape::Mask { image },
+                ..
+            }
+            | Camera::PhysicalLens {
+                aperture_shape: ApertureShape::Mask { image },
+                ..
+            } => *image = directory.join(&*image),
+            Camera::Stereo { camera, .. } => camera.rebase(directory),
+            _ => {}
+        }
+    }
+}
+
+impl Object {
+    fn rebase(&mut self, directory: &Path) {
+        match self {
+            Object::Heightfield { image, .. } => *image = directory.join(&*image),
+            Object::Instance { object, .. } => object.rebase(directory),
+            Object::Union { left, right }
+            | Object::Intersection { left, right }
+            | Object::Difference { left, right } => {
+                left.rebase(directory);
+                right.rebase(directory);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use crate::world_loader::structures::{Material, Object};
+    use crate::world_loader::Config;
+
+    /// A directory removed when the test ends, even if it fails
+    struct TempDir(PathBuf);
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_include() {
+        let directory =
+            TempDir(std::env::temp_dir().join(format!("test_include_{}", std::process::id())));
+        let directory = &directory.0;
+        fs::create_dir_all(directory.join("library")).unwrap();
+        fs::write(
+            directory.join("library/materials.ron"),
+            r#"(world: (
+                materials: {
+                    "red": Lambertian(albedo: Red),
+                    "blue": Lambertian(albedo: Blue),
+                },
+                objects: [Heightfield(image: "terrain.png", size: (1, 1), height_scale: 1, material: "red")],
+            ))"#,
+        )
+        .unwrap();
+        fs::write(
+            directory.join("scene.ron"),
+            r#"(
+                include: ["library/materials.ron"],
+                image: (height: 10, samples_per_pixel: 1, max_depth: 1),
+                camera: Orthographic(origin: (0, 0, 0), look_at: (0, 0, 1), up_vector: (0, 1, 0),
+                    aspect_ratio: 1, view_height: 1),
+                world: (
+                    background_color: Black,
+                    materials: {"blue": Metal(albedo: Blue, fuzziness: 0)},
+                    objects: [Sphere(center: (0, 0, 1), radius: 0.5, material: "blue")],
+                ),
+            )"#,
+        )
+        .unwrap();
+        fs::write(directory.join("a.ron"), r#"(include: ["b.ron"])"#).unwrap();
+        fs::write(directory.join("b.ron"), r#"(include: ["a.ron"])"#).unwrap();
+        // two files including the same library
+        fs::write(
+            directory.join("library/left.ron"),
+            r#"(include: ["materials.ron"])"#,
+        )
+        .unwrap();
+        fs::write(
+            directory.join("library/right.ron"),
+            r#"(include: ["materials.ron"])"#,
+        )
+        .unwrap();
+        let diamond = fs::read_to_string(directory.join("scene.ron"))
+            .unwrap()
+            .replace(
+                r#"["library/materials.ron"]"#,
+                r#"["library/left.ron", "library/right.ron"]"#,
+            );
+        fs::write(directory.join("diamond.ron"), diamond).unwrap();
+
+        let config = Config::parse(directory.join("scene.ron")).unwrap();
+        let cycle = Config::parse(directory.join("a.ron"));
+        let diamond = Config::parse(directory.join("diamond.ron")).unwrap();
+
+        assert_eq!(config.world.materials.len(), 2);
+        assert!(matches!(
+            config.world.materials["blue"],
+            Material::Metal { .. }
+        ));
+        assert_eq!(config.world.objects.len(), 2);
+        assert!(matches!(
+            &config.world.objects[0],
+            Object::Heightfield { image, .. } if image == Path::new("library/terrain.png")
+        ));
+        assert!(format!("{:#}", cycle.unwrap_err()).contains("cycle"));
+        assert_eq!(diamond.world.objects.len(), 2);
+    }
+}
@@ -1,17 +1,45 @@
+mod animation;
+mod include;
+mod overrides;
 mod random_scene;
 mod structures;
 
-use anyhow::{anyhow, Result};
-use ron::de::from_reader;
-use std::fs::File;
-use std::{collections::HashMap, path::Path};
+use anyhow::{anyhow, ensure, Context, Result};
+use std::fs;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
 
 use crate::{
+    bounding_boxes::BoundingBox,
     camera,
+    camera::{
+        aperture::Aperture,
+        cylindrical::Cylindrical,
+        equirectangular::Equirectangular,
+        fisheye::Fisheye,
+        lens_system::{self, LensElement, LensSystem},
+        orthographic::Orthographic,
+        stereo::{self, Stereo},
+        thin_lens::ThinLens,
+        Projection,
+    },
+    film::filter,
     hittable::hittable_list,
-    hittable::{quad, sphere, triangle, volumetric},
+    hittable::Hittable,
+    hittable::{
+        cone, csg, cylinder, disk, heightfield, instance, moving_sphere, plane, quad, sdf, sphere,
+        torus, triangle, volumetric,
+    },
     materials,
     materials::{dielectric, emissive, lambertian, metal, plastic},
+    renderer::tile,
+    sampler,
+    sampler::{
+        blue_noise::BlueNoise, halton::Halton, independent::Independent, sobol::Sobol,
+        stratified::Stratified,
+    },
     vec3::{color, Vec3},
 };
 
@@ -74,48 +102,419 @@ impl std::ops::Mul<&AspectRatio> for u32 {
     }
 }
 
-impl From<&Camera> for camera::Camera {
-    fn from(camera: &Camera) -> Self {
-        match camera {
-            Camera::ThinLens {
-                origin,
-                look_at,
-                up_vector,
-                aspect_ratio,
-                aperture,
-                vertical_fov,
-                focus_distance,
-            } => {
-                let origin = (*origin).into();
-                let look_at = (*look_at).into();
-                camera::Camera::new(
+impl From<StereoLayout> for stereo::StereoLayout {
+    fn from(layout: StereoLayout) -> Self {
+        match layout {
+            StereoLayout::SideBySide => Self::SideBySide,
+            StereoLayout::TopBottom => Self::TopBottom,
+        }
+    }
+}
+
+impl Camera {
+    /// The aspect ratio of the rendered image
+    fn aspect_ratio(&self) -> AspectRatio {
+        match self {
+            Camera::ThinLens { aspect_ratio, .. }
+            | Camera::PhysicalLens { aspect_ratio, .. }
+            | Camera::LensSystem { aspect_ratio, .. }
+            | Camera::Orthographic { aspect_ratio, .. }
+            | Camera::Isomorphic { aspect_ratio, .. }
+            | Camera::Fisheye { aspect_ratio, .. }
+            | Camera::Equirectangular { aspect_ratio, .. }
+            | Camera::Cylindrical { aspect_ratio, .. } => aspect_ratio.clone(),
+            Camera::Stereo { camera, layout, .. } => match (camera.aspect_ratio(), layout) {
+                (AspectRatio::Float(f), StereoLayout::SideBySide) => AspectRatio::Float(2.0 * f),
+                (AspectRatio::Float(f), StereoLayout::TopBottom) => AspectRatio::Float(0.5 * f),
+                (AspectRatio::Fraction(a, b), StereoLayout::SideBySide) => {
+                    AspectRatio::Fraction(2 * a, b)
+                }
+                (AspectRatio::Fraction(a, b), StereoLayout::TopBottom) => {
+                    AspectRatio::Fraction(a, 2 * b)
+                }
+            },
+        }
+    }
+}
+
+impl Camera {
+    fn build(&self, directory: &Path) -> Result<camera::Camera> {
+        let focus = |focus_distance: f64, origin: Vector, look_at: Vector| {
+            #[allow(clippy::float_cmp)]
+            if focus_distance != 0.0 {
+                focus_distance
+            } else {
+                (Vec3::from(look_at) - Vec3::from(origin)).length()
+            }
+        };
+        if let Camera::Stereo {
+            camera,
+            interocular_distance,
+            convergence,
+            layout,
+            omnidirectional,
+        } = self
+        {
+            let mut camera = camera.build(directory)?;
+            camera.projection = Box::new(Stereo::new(
+                camera.projection,
+                *interocular_distance,
+                *convergence,
+                (*layout).into(),
+                *omnidirectional,
+            ));
+            return Ok(camera);
+        }
+        let (origin, look_at, up_vector, shutter, projection): (_, _, _, _, Box<dyn Projection>) =
+            match self {
+                Camera::ThinLens {
+                    origin,
+                    look_at,
+                    up_vector,
+                    aspect_ratio,
+                    aperture,
+                    vertical_fov,
+                    focus_distance,
+                    aperture_shape,
+                    cat_eye,
+                    shift,
+                    tilt,
+                    shutter,
+                } => (
+                    origin,
+                    look_at,
+                    up_vector,
+                    shutter,
+                    Box::new(
+                        ThinLens::new(
+                            aspect_ratio.into(),
+                            *aperture,
+                            focus(*focus_distance, *origin, *look_at),
+                            *vertical_fov,
+                        )
+                        .with_aperture(aperture_shape.build(directory)?)
+                        .with_cat_eye(*cat_eye)
+                        .with_shift(*shift)
+                        .with_tilt(*tilt),
+                    ),
+                ),
+                Camera::PhysicalLens {
+                    origin,
+                    look_at,
+                    up_vector,
+                    aspect_ratio,
+                    focal_length,
+                    f_stop,
+                    sensor_height,
+                    focus_distance,
+                    aperture_shape,
+                    cat_eye,
+                    shutter,
+                } => (
+                    origin,
+                    look_at,
+                    up_vector,
+                    shutter,
+                    Box::new(
+                        ThinLens::physical(
+                            aspect_ratio.into(),
+                            *focal_length,
+                            *f_stop,
+                            *sensor_height,
+                            focus(*focus_distance, *origin, *look_at),
+                        )
+                        .with_aperture(aperture_shape.build(directory)?)
+                        .with_cat_eye(*cat_eye),
+                    ),
+                ),
+                Camera::LensSystem {
+                    origin,
+                    look_at,
+                    up_vector,
+                    aspect_ratio,
+                    lens,
+                    sensor_height,
+                    focus_distance,
+                    stop_diameter,
+                    abbe_number,
+                    shutter,
+                } => {
+                    let prescription = match lens {
+                        Lens::DoubleGauss50mm => lens_system::DOUBLE_GAUSS_50MM,
+                        Lens::WideAngle22mm => lens_system::WIDE_ANGLE_22MM,
+                        Lens::Fisheye10mm => lens_system::FISHEYE_10MM,
+                        Lens::Telephoto250mm => lens_system::TELEPHOTO_250MM,
+                        Lens::Custom(elements) => elements,
+                    };
+                    ensure!(!prescription.is_empty(), "The lens has no elements.");
+                    let mut elements: Vec<LensElement> =
+                        prescription.iter().map(Into::into).collect();
+                    let stop = elements
+                        .iter_mut()
+                        .find(|element| element.curvature_radius == 0.0)
+                        .ok_or_else(|| anyhow!("The lens has no aperture stop."))?;
+                    if *stop_diameter > 0.0 {
+                        stop.aperture = *stop_diameter;
+                    }
+                    let focus_distance = focus(*focus_distance, *origin, *look_at);
+                    let lens = LensSystem::new(
+                        elements,
+                        aspect_ratio.into(),
+                        *sensor_height,
+                        focus_distance,
+                        *abbe_number,
+                    )
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "The lens cannot focus at {} meters, or lets no light through.",
+                            focus_distance
+                        )
+                    })?;
+                    (origin, look_at, up_vector, shutter, Box::new(lens))
+                }
+                Camera::Orthographic {
+                    origin,
+                    look_at,
+                    up_vector,
+                    aspect_ratio,
+                    view_height,
+                    shutter,
+                } => (
+                    origin,
+                    look_at,
+                    up_vector,
+                    shutter,
+                    Box::new(Orthographic::new(aspect_ratio.into(), *view_height)),
+                ),
+                Camera::Isomorphic {
+                    origin,
+                    look_at,
+                    up_vector,
+                    aspect_ratio,
+                    vertical_fov,
+                    shutter,
+                } => (
+                    origin,
+                    look_at,
+                    up_vector,
+                    shutter,
+                    Box::new(Orthographic::new(
+                        aspect_ratio.into(),
+                        2.0 * (0.5 * vertical_fov.to_radians()).tan(),
+                    )),
+                ),
+                Camera::Fisheye {
+                    origin,
+                    look_at,
+                    up_vector,
+                    aspect_ratio,
+                    fov,
+                    shutter,
+                } => (
+                    origin,
+                    look_at,
+                    up_vector,
+                    shutter,
+                    Box::new(Fisheye::new(aspect_ratio.into(), *fov)),
+                ),
+                Camera::Equirectangular {
                     origin,
                     look_at,
-                    (*up_vector).into(),
-                    aspect_ratio.into(),
-                    *aperture,
-                    #[allow(clippy::float_cmp)]
-                    if focus_distance != &0.0 {
-                        *focus_distance
-                    } else {
-                        (look_at - origin).length()
+                    up_vector,
+                    shutter,
+                    ..
+                } => (
+                    origin,
+                    look_at,
+                    up_vector,
+                    shutter,
+                    Box::new(Equirectangular),
+                ),
+                Camera::Cylindrical {
+                    origin,
+                    look_at,
+                    up_vector,
+                    aspect_ratio,
+                    horizontal_fov,
+                    shutter,
+                } => (
+                    origin,
+                    look_at,
+                    up_vector,
+                    shutter,
+                    Box::new(Cylindrical::new(aspect_ratio.into(), *horizontal_fov)),
+                ),
+                Camera::Stereo { .. } => unreachable!("stereo cameras are handled above"),
+            };
+        Ok(camera::Camera::new(
+            (*origin).into(),
+            (*look_at).into(),
+            (*up_vector).into(),
+            projection,
+        )
+        .with_shutter(*shutter))
+    }
+}
+
+impl Camera {
+    fn referenced_files(&self) -> Vec<&Path> {
+        match self {
+            Camera::ThinLens {
+                aperture_shape: ApertureShape::Mask { image },
+                ..
+            }
+            | Camera::PhysicalLens {
+                aperture_shape: ApertureShape::Mask { image },
+                ..
+            } => vec![image.as_path()],
+            Camera::Stereo { camera, .. } => camera.referenced_files(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Object {
+    fn referenced_files(&self) -> Vec<&Path> {
+        match self {
+            Object::Heightfield { image, .. } => vec![image.as_path()],
+            Object::Instance { object, .. } => object.referenced_files(),
+            Object::Union { left, right }
+            | Object::Intersection { left, right }
+            | Object::Difference { left, right } => {
+                let mut files = left.referenced_files();
+                files.extend(right.referenced_files());
+                files
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl ApertureShape {
+    fn build(&self, directory: &Path) -> Result<Aperture> {
+        Ok(match self {
+            ApertureShape::Circle => Aperture::Circle,
+            ApertureShape::Polygon { blades, rotation } => {
+                Aperture::polygon(*blades, rotation.to_radians()).ok_or_else(|| {
+                    anyhow!("An aperture needs at least 3 blades, it has {}.", blades)
+                })?
+            }
+            ApertureShape::Mask { image } => {
+                let path = directory.join(image);
+                let mask = image::open(&path)
+                    .with_context(|| {
+                        format!("Could not open aperture mask image `{}`", path.display())
+                    })?
+                    .into_luma8();
+                let weights: Vec<f64> = mask.pixels().map(|p| p.0[0] as f64).collect();
+                Aperture::mask(mask.width() as usize, mask.height() as usize, &weights).ok_or_else(
+                    || {
+                        anyhow!(
+                            "Aperture mask image `{}` is completely black.",
+                            path.display()
+                        )
                     },
-                    *vertical_fov,
-                )
+                )?
             }
-            Camera::Isomorphic {
-                origin,
-                look_at,
-                up_vector,
-                aspect_ratio,
-                vertical_fov,
-            } => camera::Camera::isomorphic(
-                (*origin).into(),
-                (*look_at).into(),
-                (*up_vector).into(),
-                aspect_ratio.into(),
-                *vertical_fov,
-            ),
+        })
+    }
+}
+
+impl From<TileOrder> for tile::TileOrder {
+    fn from(order: TileOrder) -> Self {
+        match order {
+            TileOrder::CentreOut => Self::CentreOut,
+            TileOrder::Hilbert => Self::Hilbert,
+        }
+    }
+}
+
+impl From<&Filter> for filter::Filter {
+    fn from(filter: &Filter) -> Self {
+        match *filter {
+            Filter::Box { radius } => Self::Box { radius },
+            Filter::Tent { radius } => Self::Tent { radius },
+            Filter::Gaussian { radius, alpha } => Self::Gaussian { radius, alpha },
+            Filter::Mitchell { radius, b, c } => Self::Mitchell { radius, b, c },
+            Filter::Lanczos { radius } => Self::Lanczos { radius },
+        }
+    }
+}
+
+impl From<&DistanceField> for sdf::DistanceField {
+    fn from(shape: &DistanceField) -> Self {
+        match shape {
+            DistanceField::Sphere { center, radius } => sdf::DistanceField::Sphere {
+                center: (*center).into(),
+                radius: *radius,
+            },
+            DistanceField::Box {
+                center,
+                half_extents,
+            } => sdf::DistanceField::Box {
+                center: (*center).into(),
+                half_extents: (*half_extents).into(),
+            },
+            DistanceField::RoundBox {
+                center,
+                half_extents,
+                radius,
+            } => sdf::DistanceField::RoundBox {
+                center: (*center).into(),
+                half_extents: (*half_extents).into(),
+                radius: *radius,
+            },
+            DistanceField::Torus {
+                center,
+                major_radius,
+                minor_radius,
+            } => sdf::DistanceField::Torus {
+                center: (*center).into(),
+                major_radius: *major_radius,
+                minor_radius: *minor_radius,
+            },
+            DistanceField::Capsule { a, b, radius } => sdf::DistanceField::Capsule {
+                a: (*a).into(),
+                b: (*b).into(),
+                radius: *radius,
+            },
+            DistanceField::Mandelbulb {
+                center,
+                scale,
+                power,
+                iterations,
+            } => sdf::DistanceField::Mandelbulb {
+                center: (*center).into(),
+                scale: *scale,
+                power: *power,
+                iterations: *iterations,
+            },
+            DistanceField::Union {
+                left,
+                right,
+                smoothness,
+            } => sdf::DistanceField::Union {
+                left: Box::new(left.as_ref().into()),
+                right: Box::new(right.as_ref().into()),
+                smoothness: *smoothness,
+            },
+            DistanceField::Subtraction {
+                left,
+                right,
+                smoothness,
+            } => sdf::DistanceField::Subtraction {
+                left: Box::new(left.as_ref().into()),
+                right: Box::new(right.as_ref().into()),
+                smoothness: *smoothness,
+            },
+            DistanceField::Repetition { period, shape } => sdf::DistanceField::Repetition {
+                period: (*period).into(),
+                shape: Box::new(shape.as_ref().into()),
+            },
+            DistanceField::Twist { amount, shape } => sdf::DistanceField::Twist {
+                amount: *amount,
+                shape: Box::new(shape.as_ref().into()),
+            },
         }
     }
 }
@@ -146,18 +545,74 @@ impl From<&Material> for Box<dyn materials::Material> {
 
 impl Config {
     pub fn parse<P: AsRef<Path>>(filename: P) -> Result<Self> {
-        let file = File::open(filename)?;
-        from_reader::<File, Config>(file).map_err(|e| anyhow!(e))
+        let text = fs::read_to_string(&filename)?;
+        let directory = filename
+            .as_ref()
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        Self::load(&text, directory, Some(filename.as_ref()))
+    }
+
+    /// Parses the content of a config file, whose relative paths are resolved from `directory`
+    pub fn parse_str(text: &str, directory: PathBuf) -> Result<Self> {
+        Self::load(text, directory, None)
     }
 
-    pub fn camera(&self) -> camera::Camera {
-        (&self.camera).into()
+    /// The files the scene loads, such as images, and the files it includes
+    pub fn referenced_files(&self) -> Vec<PathBuf> {
+        self.camera
+            .referenced_files()
+            .into_iter()
+            .chain(self.world.objects.iter().flat_map(Object::referenced_files))
+            .map(|file| self.directory.join(file))
+            .chain(self.included_files.iter().cloned())
+            .collect()
     }
 
-    pub fn aspect_ratio(&self) -> &AspectRatio {
-        match &self.camera {
-            Camera::ThinLens { aspect_ratio, .. } => aspect_ratio,
-            Camera::Isomorphic { aspect_ratio, .. } => aspect_ratio,
+    pub fn camera(&self) -> Result<camera::Camera> {
+        self.camera.build(&self.directory)
+    }
+
+    /// A hash of everything that changes the value of the samples, for checking
+    /// that a checkpoint belongs to this scene. The number of samples and the
+    /// adaptive sampling can change between renders, except with a stratified
+    /// sampler. Files referenced by the scene, such as images, are not hashed.
+    pub fn scene_hash(&self) -> u64 {
+        let materials: BTreeMap<_, _> = self.world.materials.iter().collect();
+        let stratification =
+            matches!(self.image.sampler, Sampler::Stratified).then(|| self.image.samples_per_pixel);
+        let scene = (
+            self.image.height,
+            self.image.max_depth,
+            &self.image.filter,
+            &self.image.sampler,
+            stratification,
+            &self.camera,
+            &self.world.background_color,
+            materials,
+            &self.world.objects,
+        );
+        // FNV-1a, which unlike the hasher of the standard library is stable
+        ron::ser::to_string(&scene)
+            .unwrap_or_default()
+            .bytes()
+            .fold(0xcbf29ce484222325, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+            })
+    }
+
+    pub fn aspect_ratio(&self) -> AspectRatio {
+        self.camera.aspect_ratio()
+    }
+
+    pub fn sampler(&self) -> Box<dyn sampler::Sampler> {
+        match self.image.sampler {
+            Sampler::Independent => Box::new(Independent),
+            Sampler::Stratified => Box::new(Stratified::new(self.image.samples_per_pixel)),
+            Sampler::Halton => Box::new(Halton::new()),
+            Sampler::Sobol => Box::new(Sobol::new()),
+            Sampler::BlueNoise => Box::new(BlueNoise::new()),
         }
     }
 
@@ -176,79 +631,315 @@ impl Config {
         let mut world = hittable_list::HittableList::new();
 
         for object in &self.world.objects {
-            match object {
-                Object::Sphere {
-                    center,
-                    radius,
-                    material,
-                } => {
-                    let material = materials.get(material).ok_or_else(|| {
-                        anyhow!(
-                            "Could not add sphere with material \"{}\": undeclared material.",
-                            material
-                        )
-                    })?;
-                    world.push(Box::new(sphere::Sphere::new(
-                        (*center).into(),
-                        *radius,
-                        material.as_ref(),
-                    )))
-                }
-                Object::Triangle {
-                    vertex,
-                    edges: (a, b),
-                    material,
-                } => {
-                    let material = materials.get(material).ok_or_else(|| {
-                        anyhow!(
-                            "Could not add triangle with material \"{}\": undeclared material.",
-                            material
-                        )
-                    })?;
-                    world.push(Box::new(triangle::Triangle::new(
-                        (*vertex).into(),
-                        ((*a).into(), (*b).into()),
-                        material.as_ref(),
-                    )));
-                }
-                Object::Quad {
-                    vertex,
-                    edges: (a, b),
-                    material,
-                } => {
-                    let material = materials.get(material).ok_or_else(|| {
-                        anyhow!(
-                            "Could not add quad with material \"{}\": undeclared material.",
-                            material
-                        )
-                    })?;
-                    world.push(Box::new(quad::Quad::new(
-                        (*vertex).into(),
-                        ((*a).into(), (*b).into()),
-                        material.as_ref(),
-                    )));
-                }
-                Object::Volumetric {
-                    center,
-                    radius,
-                    density,
-                    material,
-                } => {
-                    let material = materials.get(material).ok_or_else(|| {
-                        anyhow!(
-                            "Could not add quad with material \"{}\": undeclared material.",
-                            material
-                        )
-                    })?;
-                    world.push(Box::new(volumetric::Volume::new(
-                        (*center).into(),
-                        *radius,
-                        *density,
-                        material.as_ref(),
-                    )));
-                }
-            }
+            world.push(self.object(object, materials)?);
         }
         Ok(world)
     }
+
+    fn object<'a>(
+        &self,
+        object: &'a Object,
+        materials: &'a HashMap<&'a String, Box<dyn materials::Material + 'a>>,
+    ) -> Result<Box<dyn Hittable + 'a>> {
+        Ok(match object {
+            Object::Sphere {
+                center,
+                radius,
+                material,
+            } => {
+                let material = materials.get(material).ok_or_else(|| {
+                    anyhow!(
+                        "Could not add sphere with material \"{}\": undeclared material.",
+                        material
+                    )
+                })?;
+                Box::new(sphere::Sphere::new(
+                    (*center).into(),
+                    *radius,
+                    material.as_ref(),
+                ))
+            }
+            Object::Triangle {
+                vertex,
+                edges: (a, b),
+                material,
+            } => {
+                let material = materials.get(material).ok_or_else(|| {
+                    anyhow!(
+                        "Could not add triangle with material \"{}\": undeclared material.",
+                        material
+                    )
+                })?;
+                Box::new(triangle::Triangle::new(
+                    (*vertex).into(),
+                    ((*a).into(), (*b).into()),
+                    material.as_ref(),
+                ))
+            }
+            Object::Quad {
+                vertex,
+                edges: (a, b),
+                material,
+            } => {
+                let material = materials.get(material).ok_or_else(|| {
+                    anyhow!(
+                        "Could not add quad with material \"{}\": undeclared material.",
+                        material
+                    )
+                })?;
+                Box::new(quad::Quad::new(
+                    (*vertex).into(),
+                    ((*a).into(), (*b).into()),
+                    material.as_ref(),
+                ))
+            }
+            Object::Volumetric {
+                center,
+                radius,
+                density,
+                material,
+            } => {
+                let material = materials.get(material).ok_or_else(|| {
+                    anyhow!(
+                        "Could not add quad with material \"{}\": undeclared material.",
+                        material
+                    )
+                })?;
+                Box::new(volumetric::Volume::new(
+                    (*center).into(),
+                    *radius,
+                    *density,
+                    material.as_ref(),
+                ))
+            }
+            Object::Cylinder {
+                base,
+                top,
+                radius,
+                open,
+                material,
+            } => {
+                ensure!(
+                    (Vec3::from(*top) - Vec3::from(*base)).length() > 0.0,
+                    "Could not add cylinder: its base and top are the same point."
+                );
+                ensure!(
+                    *radius > 0.0,
+                    "Could not add cylinder: its radius must be positive."
+                );
+                let material = materials.get(material).ok_or_else(|| {
+                    anyhow!(
+                        "Could not add cylinder with material \"{}\": undeclared material.",
+                        material
+                    )
+                })?;
+                Box::new(cylinder::Cylinder::new(
+                    (*base).into(),
+                    (*top).into(),
+                    *radius,
+                    !open,
+                    material.as_ref(),
+                ))
+            }
+            Object::Cone {
+                base,
+                apex,
+                radius,
+                open,
+                material,
+            } => {
+                ensure!(
+                    (Vec3::from(*apex) - Vec3::from(*base)).length() > 0.0,
+                    "Could not add cone: its base and apex are the same point."
+                );
+                ensure!(
+                    *radius > 0.0,
+                    "Could not add cone: its radius must be positive."
+                );
+                let material = materials.get(material).ok_or_else(|| {
+                    anyhow!(
+                        "Could not add cone with material \"{}\": undeclared material.",
+                        material
+                    )
+                })?;
+                Box::new(cone::Cone::new(
+                    (*base).into(),
+                    (*apex).into(),
+                    *radius,
+                    !open,
+                    material.as_ref(),
+                ))
+            }
+            Object::Disk {
+                center,
+                normal,
+                radius,
+                material,
+            } => {
+                let material = materials.get(material).ok_or_else(|| {
+                    anyhow!(
+                        "Could not add disk with material \"{}\": undeclared material.",
+                        material
+                    )
+                })?;
+                Box::new(disk::Disk::new(
+                    (*center).into(),
+                    (*normal).into(),
+                    *radius,
+                    material.as_ref(),
+                ))
+            }
+            Object::Torus {
+                center,
+                axis,
+                major_radius,
+                minor_radius,
+                material,
+            } => {
+                let material = materials.get(material).ok_or_else(|| {
+                    anyhow!(
+                        "Could not add torus with material \"{}\": undeclared material.",
+                        material
+                    )
+                })?;
+                Box::new(torus::Torus::new(
+                    (*center).into(),
+                    (*axis).into(),
+                    *major_radius,
+                    *minor_radius,
+                    material.as_ref(),
+                ))
+            }
+            Object::Plane {
+                point,
+                normal,
+                material,
+            } => {
+                let material = materials.get(material).ok_or_else(|| {
+                    anyhow!(
+                        "Could not add plane with material \"{}\": undeclared material.",
+                        material
+                    )
+                })?;
+                Box::new(plane::Plane::new(
+                    (*point).into(),
+                    (*normal).into(),
+                    material.as_ref(),
+                ))
+            }
+            Object::Sdf {
+                shape,
+                bounds: (minimum, maximum),
+                material,
+            } => {
+                let material = materials.get(material).ok_or_else(|| {
+                    anyhow!(
+                        "Could not add SDF with material \"{}\": undeclared material.",
+                        material
+                    )
+                })?;
+                Box::new(sdf::Sdf::new(
+                    shape.into(),
+                    BoundingBox {
+                        minimum: (*minimum).into(),
+                        maximum: (*maximum).into(),
+                    },
+                    material.as_ref(),
+                ))
+            }
+            Object::Heightfield {
+                image,
+                origin,
+                size,
+                height_scale,
+                material,
+            } => {
+                let material = materials.get(material).ok_or_else(|| {
+                    anyhow!(
+                        "Could not add heightfield with material \"{}\": undeclared material.",
+                        material
+                    )
+                })?;
+                let path = self.directory.join(image);
+                let image = image::open(&path)
+                    .with_context(|| {
+                        format!("Could not open heightfield image `{}`", path.display())
+                    })?
+                    .into_luma16();
+                ensure!(
+                    image.width() >= 2 && image.height() >= 2,
+                    "The heightfield image `{}` is {}x{} pixels, at least 2x2 are needed.",
+                    path.display(),
+                    image.width(),
+                    image.height()
+                );
+                let heights = image
+                    .pixels()
+                    .map(|pixel| pixel.0[0] as f64 / u16::MAX as f64 * height_scale)
+                    .collect();
+                Box::new(heightfield::Heightfield::new(
+                    (*origin).into(),
+                    *size,
+                    (image.width() as usize, image.height() as usize),
+                    heights,
+                    material.as_ref(),
+                ))
+            }
+            Object::MovingSphere {
+                start,
+                end,
+                radius,
+                material,
+            } => {
+                let material = materials.get(material).ok_or_else(|| {
+                    anyhow!(
+                        "Could not add moving sphere with material \"{}\": undeclared material.",
+                        material
+                    )
+                })?;
+                Box::new(moving_sphere::MovingSphere::new(
+                    ((*start).into(), (*end).into()),
+                    *radius,
+                    material.as_ref(),
+                ))
+            }
+            Object::Instance { object, keyframes } => {
+                ensure!(
+                    !keyframes.is_empty(),
+                    "Could not add instance: at least one keyframe is needed."
+                );
+                ensure!(
+                    keyframes.iter().all(|keyframe| keyframe.time.is_finite()),
+                    "Could not add instance: the times of its keyframes must be finite."
+                );
+                Box::new(instance::Instance::new(
+                    self.object(object, materials)?,
+                    keyframes
+                        .iter()
+                        .map(|keyframe| instance::Keyframe {
+                            time: keyframe.time,
+                            translation: keyframe.translation.into(),
+                            rotation: keyframe.rotation.into(),
+                            scale: keyframe.scale,
+                        })
+                        .collect(),
+                ))
+            }
+            Object::Union { left, right } => Box::new(csg::Csg::new(
+                csg::Operation::Union,
+                self.object(left, materials)?,
+                self.object(right, materials)?,
+            )),
+            Object::Intersection { left, right } => Box::new(csg::Csg::new(
+                csg::Operation::Intersection,
+                self.object(left, materials)?,
+                self.object(right, materials)?,
+            )),
+            Object::Difference { left, right } => Box::new(csg::Csg::new(
+                csg::Operation::Difference,
+                self.object(left, materials)?,
+                self.object(right, materials)?,
+            )),
+        })
+    }
 }
@@ -0,0 +1,289 @@
+//! Overriding values of a config from the command line, such as
+//! `camera.aperture=0.2`: the config is converted to a JSON value, the value at
+//! the path is replaced, and the JSON value is converted back.
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use serde_json::{Map, Number, Value};
+
+use super::Config;
+
+impl Config {
+    /// Sets the value at a dotted path, such as `camera.aperture` or
+    /// `world.objects.0.radius`, to a value written as in a config file
+    pub fn set(&mut self, path: &str, value: &str) -> Result<()> {
+        let value = parse_value(value).with_context(|| format!("Could not parse `{}`", value))?;
+        *self = self.with_values(vec![(path, value)])?;
+        Ok(())
+    }
+
+    /// A copy of the config with the values at some paths replaced
+    pub(super) fn with_values(&self, values: Vec<(&str, Value)>) -> Result<Config> {
+        let mut json = serde_json::to_value(self)?;
+        for (path, value) in &values {
+            let mut target = &mut json;
+            for (i, key) in path.split('.').enumerate() {
+                target = field(target, key).ok_or_else(|| match i {
+                    0 => anyhow!("No `{}` in the config", key),
+                    _ => anyhow!(
+                        "No `{}` in `{}`",
+                        key,
+                        path.split('.').take(i).collect::<Vec<_>>().join(".")
+                    ),
+                })?;
+            }
+            assign(target, value.clone());
+        }
+
+        let mut config: Config = serde_json::from_value(json).with_context(|| {
+            let paths: Vec<_> = values.iter().map(|(path, _)| *path).collect();
+            format!("Invalid value for `{}`", paths.join("`, `"))
+        })?;
+        config.directory = self.directory.clone();
+        config.included_files = self.included_files.clone();
+        Ok(config)
+    }
+}
+
+/// Whether a JSON value is an enum variant with fields, such as `{"Rgb": [1, 0, 0]}`
+fn is_variant(map: &Map<String, Value>) -> bool {
+    map.len() == 1
+        && map
+            .keys()
+            .all(|name| name.starts_with(|c: char| c.is_ascii_uppercase()))
+}
+
+/// Replaces a value, keeping its enum variant if the new value has none, so
+/// that `(1, 0, 0)` replaces `Rgb(0.5, 0.5, 0.5)` with `Rgb(1, 0, 0)`
+fn assign(target: &mut Value, value: Value) {
+    match target {
+        Value::Object(map) if is_variant(map) && !value.is_object() && !value.is_string() => {
+            *map.values_mut().next().unwrap() = value
+        }
+        _ => *target = value,
+    }
+}
+
+fn field<'a>(value: &'a mut Value, key: &str) -> Option<&'a mut Value> {
+    match value {
+        Value::Object(map) => {
+            if map.contains_key(key) {
+                return map.get_mut(key);
+            }
+            // the fields of an enum variant, such as `{"ThinLens": {"aperture": 0.1, ...}}`
+            if is_variant(map) {
+                field(map.values_mut().next().unwrap(), key)
+            } else {
+                None
+            }
+        }
+        Value::Array(items) => key.parse().ok().and_then(move |i: usize| items.get_mut(i)),
+        _ => None,
+    }
+}
+
+/// Parses a RON value into the JSON value serde would produce for it. Unlike
+/// `ron::Value`, this keeps the names of enum variants.
+fn parse_value(text: &str) -> Result<Value> {
+    let mut parser = Parser(text);
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    ensure!(parser.0.is_empty(), "Unexpected `{}`", parser.0);
+    Ok(value)
+}
+
+struct Parser<'a>(&'a str);
+
+fn is_identifier(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        self.0 = self.0.trim_start();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        match self.0.strip_prefix(c) {
+            Some(rest) => {
+                self.0 = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        ensure!(self.eat(c), "Expecting `{}` at `{}`", c, self.0);
+        Ok(())
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let end = self.0.find(|c| !predicate(c)).unwrap_or(self.0.len());
+        let (token, rest) = self.0.split_at(end);
+        self.0 = rest;
+        token
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        self.skip_whitespace();
+        match self.0.chars().next() {
+            Some('"') => self.string(),
+            Some('[') => {
+                self.expect('[')?;
+                Ok(Value::Array(self.sequence(']')?))
+            }
+            Some('{') => self.map(),
+            Some('(') => self.parenthesised(),
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                let name = self.take_while(is_identifier);
+                match name {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    "None" => Ok(Value::Null),
+                    "Some" => {
+                        self.expect('(')?;
+                        let value = self.value()?;
+                        self.expect(')')?;
+                        Ok(value)
+                    }
+                    _ if self.0.trim_start().starts_with('(') => {
+                        let value = match self.parenthesised()? {
+                            // a newtype variant, such as `Hex(0xFF0000)`
+                            Value::Array(mut items) if items.len() == 1 => items.remove(0),
+                            value => value,
+                        };
+                        let mut variant = Map::new();
+                        variant.insert(name.to_owned(), value);
+                        Ok(Value::Object(variant))
+                    }
+                    // a unit variant, such as `Halton`
+                    _ => Ok(Value::String(name.to_owned())),
+                }
+            }
+            Some(_) => self.number(),
+            None => bail!("Expecting a value"),
+        }
+    }
+
+    fn string(&mut self) -> Result<Value> {
+        let mut escaped = false;
+        let end = self.0[1..]
+            .find(|c| {
+                let end = c == '"' && !escaped;
+                escaped = c == '\\' && !escaped;
+                end
+            })
+            .ok_or_else(|| anyhow!("Unterminated string"))?;
+        let (string, rest) = self.0.split_at(end + 2);
+        self.0 = rest;
+        Ok(serde_json::from_str(string)?)
+    }
+
+    fn number(&mut self) -> Result<Value> {
+        let token = self.take_while(|c| c.is_ascii_alphanumeric() || "+-._".contains(c));
+        let digits = token.replace('_', "");
+        let number = if let Some(hex) = digits.strip_prefix("0x") {
+            i64::from_str_radix(hex, 16).ok().map(Number::from)
+        } else if let Ok(integer) = digits.parse::<i64>() {
+            Some(Number::from(integer))
+        } else {
+            digits.parse().ok().and_then(Number::from_f64)
+        };
+        number
+            .map(Value::Number)
+            .ok_or_else(|| anyhow!("Invalid value `{}`", token))
+    }
+
+    /// Items separated by commas, up to `close`
+    fn sequence(&mut self, close: char) -> Result<Vec<Value>> {
+        let mut items = Vec::new();
+        loop {
+            if self.eat(close) {
+                return Ok(items);
+            }
+            items.push(self.value()?);
+            if !self.eat(',') {
+                self.expect(close)?;
+                return Ok(items);
+            }
+        }
+    }
+
+    fn map(&mut self) -> Result<Value> {
+        self.expect('{')?;
+        let mut map = Map::new();
+        loop {
+            if self.eat('}') {
+                return Ok(Value::Object(map));
+            }
+            let key = match self.value()? {
+                Value::String(key) => key,
+                key => key.to_string(),
+            };
+            self.expect(':')?;
+            map.insert(key, self.value()?);
+            if !self.eat(',') {
+                self.expect('}')?;
+                return Ok(Value::Object(map));
+            }
+        }
+    }
+
+    /// A tuple, or a struct if the first item is named
+    fn parenthesised(&mut self) -> Result<Value> {
+        self.expect('(')?;
+        let rest = self.0.trim_start();
+        let name = rest.find(|c| !is_identifier(c)).unwrap_or(rest.len());
+        if name == 0 || !rest[name..].trim_start().starts_with(':') {
+            return Ok(Value::Array(self.sequence(')')?));
+        }
+
+        let mut fields = Map::new();
+        loop {
+            if self.eat(')') {
+                return Ok(Value::Object(fields));
+            }
+            self.skip_whitespace();
+            let name = self.take_while(is_identifier).to_owned();
+            self.expect(':')?;
+            fields.insert(name, self.value()?);
+            if !self.eat(',') {
+                self.expect(')')?;
+                return Ok(Value::Object(fields));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::world_loader::Config;
+    use crate::FastRng;
+
+    #[test]
+    fn test_set() {
+        let mut config = Config::random_scene(&mut FastRng::new(1));
+        config.set("image.samples_per_pixel", "12").unwrap();
+        config.set("image.sampler", "Halton").unwrap();
+        config.set("camera.aperture", "0.25").unwrap();
+        config.set("camera.origin", "(1, 2, 3)").unwrap();
+        config
+            .set("world.background_color", "Rgb(0.1, 0.2, 0.3)")
+            .unwrap();
+        config
+            .set("image.filter", "Gaussian(radius: 1.5, alpha: 2)")
+            .unwrap();
+
+        let text = ron::ser::to_string(&config).unwrap();
+        assert!(text.contains("samples_per_pixel:12"));
+        assert!(text.contains("sampler:Halton"));
+        assert!(text.contains("aperture:0.25"));
+        assert!(text.contains("origin:(1,2,3)"));
+        assert!(text.contains("background_color:Rgb(0.1,0.2,0.3)"));
+        assert!(text.contains("filter:Gaussian(radius:1.5,alpha:2)"));
+
+        assert!(config.set("camera.apperture", "0.1").is_err());
+        assert!(config.set("image.height", "\"high\"").is_err());
+    }
+}
@@ -10,6 +10,12 @@ impl Config {
             height: 405,
             samples_per_pixel: 100,
             max_depth: 20,
+            filter: Filter::default(),
+            sampler: Sampler::default(),
+            adaptive_threshold: 0.0,
+            min_samples_per_pixel: default_min_samples_per_pixel(),
+            tile_size: default_tile_size(),
+            tile_order: TileOrder::CentreOut,
         };
         let camera = Camera::ThinLens {
             origin: (13.0, 2.0, 3.0),
@@ -19,6 +25,11 @@ impl Config {
             vertical_fov: 30.0,
             aperture: 0.1,
             focus_distance: 10.0,
+            aperture_shape: ApertureShape::Circle,
+            cat_eye: 0.0,
+            shift: (0.0, 0.0),
+            tilt: (0.0, 0.0),
+            shutter: default_shutter(),
         };
         let mut world = World {
             background_color: Color::White,
@@ -32,9 +43,9 @@ impl Config {
                 albedo: Color::Rgb(0.5, 0.5, 0.5),
             },
         );
-        world.objects.push(Object::Sphere {
-            center: (0.0, -1000.0, 0.0),
-            radius: 1000.0,
+        world.objects.push(Object::Plane {
+            point: (0.0, 0.0, 0.0),
+            normal: (0.0, 1.0, 0.0),
             material: String::from("ground"),
         });
         world.materials.insert(
@@ -135,6 +146,9 @@ impl Config {
             image,
             camera,
             world,
+            animation: None,
+            directory: Default::default(),
+            included_files: Vec::new(),
         }
     }
 }
@@ -1,5 +1,6 @@
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 
 pub type Vector = (f64, f64, f64);
 
@@ -8,6 +9,50 @@ pub struct Config {
     pub image: Image,
     pub camera: Camera,
     pub world: World,
+    #[serde(default)]
+    pub animation: Option<Animation>,
+    /// The directory of the config file, which relative paths are resolved from
+    #[serde(skip)]
+    pub directory: PathBuf,
+    /// The files the config file includes, directly or not
+    #[serde(skip)]
+    pub included_files: Vec<PathBuf>,
+}
+
+/// A config file as written, whose values may come from the files it includes.
+/// Included files are config files too, with any of the values of a config.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename = "Config")]
+pub struct ConfigFile {
+    /// Paths relative to the file
+    #[serde(default)]
+    pub include: Vec<PathBuf>,
+    #[serde(default, deserialize_with = "some")]
+    pub image: Option<Image>,
+    #[serde(default, deserialize_with = "some")]
+    pub camera: Option<Camera>,
+    #[serde(default)]
+    pub world: WorldFile,
+    #[serde(default)]
+    pub animation: Option<Animation>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename = "World")]
+pub struct WorldFile {
+    #[serde(default, deserialize_with = "some")]
+    pub background_color: Option<Color>,
+    #[serde(default)]
+    pub materials: HashMap<String, Material>,
+    #[serde(default)]
+    pub objects: Vec<Object>,
+}
+
+/// Deserializes an optional value written without `Some`
+fn some<'de, D: Deserializer<'de>, T: Deserialize<'de>>(
+    deserializer: D,
+) -> Result<Option<T>, D::Error> {
+    T::deserialize(deserializer).map(Some)
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -15,6 +60,147 @@ pub struct Image {
     pub height: u32,
     pub samples_per_pixel: u32,
     pub max_depth: u32,
+    #[serde(default)]
+    pub filter: Filter,
+    #[serde(default)]
+    pub sampler: Sampler,
+    /// Stops sampling a pixel once the estimated relative error of its mean is
+    /// below this threshold, `samples_per_pixel` being then the maximum. Disabled if 0.
+    #[serde(default)]
+    pub adaptive_threshold: f64,
+    #[serde(default = "default_min_samples_per_pixel")]
+    pub min_samples_per_pixel: u32,
+    /// The side of the square tiles the image is rendered by, in pixels
+    #[serde(default = "default_tile_size")]
+    pub tile_size: u32,
+    #[serde(default)]
+    pub tile_order: TileOrder,
+}
+
+pub fn default_min_samples_per_pixel() -> u32 {
+    16
+}
+
+pub fn default_tile_size() -> u32 {
+    16
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub enum TileOrder {
+    #[default]
+    CentreOut,
+    Hilbert,
+}
+
+/// Values of the config changing from frame to frame
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Animation {
+    /// The tracks of the animated values, by their path in the config such as
+    /// `camera.origin` or `world.materials.metal.fuzziness`
+    pub tracks: BTreeMap<String, Track>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Track {
+    #[serde(default)]
+    pub interpolation: Interpolation,
+    pub keys: Vec<Key>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Key {
+    pub frame: f64,
+    /// Numbers, and tuples of numbers, are interpolated. Other values hold until
+    /// the next key.
+    pub value: serde_json::Value,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Interpolation {
+    #[default]
+    Linear,
+    /// A spline passing through the keys
+    CatmullRom,
+    /// Eases in and out of each key, as a cubic Bézier curve with flat handles
+    Bezier,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub enum Sampler {
+    Independent,
+    Stratified,
+    Halton,
+    #[default]
+    Sobol,
+    BlueNoise,
+}
+
+/// Pixel reconstruction filters, with radii in pixels
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Filter {
+    Box {
+        #[serde(default = "default_box_radius")]
+        radius: f64,
+    },
+    Tent {
+        #[serde(default = "default_tent_radius")]
+        radius: f64,
+    },
+    Gaussian {
+        #[serde(default = "default_gaussian_radius")]
+        radius: f64,
+        #[serde(default = "default_gaussian_alpha")]
+        alpha: f64,
+    },
+    Mitchell {
+        #[serde(default = "default_mitchell_radius")]
+        radius: f64,
+        #[serde(default = "default_mitchell_parameter")]
+        b: f64,
+        #[serde(default = "default_mitchell_parameter")]
+        c: f64,
+    },
+    Lanczos {
+        #[serde(default = "default_lanczos_radius")]
+        radius: f64,
+    },
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self::Box {
+            radius: default_box_radius(),
+        }
+    }
+}
+
+pub fn default_box_radius() -> f64 {
+    0.5
+}
+
+pub fn default_tent_radius() -> f64 {
+    1.0
+}
+
+pub fn default_gaussian_radius() -> f64 {
+    1.5
+}
+
+pub fn default_gaussian_alpha() -> f64 {
+    2.0
+}
+
+pub fn default_mitchell_radius() -> f64 {
+    2.0
+}
+
+/// The parameters recommended by Mitchell and Netravali, b = c = 1/3
+pub fn default_mitchell_parameter() -> f64 {
+    1.0 / 3.0
+}
+
+pub fn default_lanczos_radius() -> f64 {
+    3.0
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -28,14 +214,169 @@ pub enum Camera {
         vertical_fov: f64,
         #[serde(default)]
         focus_distance: f64,
+        #[serde(default)]
+        aperture_shape: ApertureShape,
+        #[serde(default)]
+        cat_eye: f64,
+        /// Lens shift, as fractions of the width and the height of the image
+        #[serde(default)]
+        shift: (f64, f64),
+        /// Tilt of the plane in focus in degrees, around the horizontal and the vertical axes
+        #[serde(default)]
+        tilt: (f64, f64),
+        #[serde(default = "default_shutter")]
+        shutter: (f64, f64),
+    },
+    /// A thin lens described like a real camera. The focal length and the sensor
+    /// height are in millimeters, and the scene is assumed to be in meters.
+    PhysicalLens {
+        origin: Vector,
+        look_at: Vector,
+        up_vector: Vector,
+        aspect_ratio: AspectRatio,
+        focal_length: f64,
+        f_stop: f64,
+        #[serde(default = "default_sensor_height")]
+        sensor_height: f64,
+        #[serde(default)]
+        focus_distance: f64,
+        #[serde(default)]
+        aperture_shape: ApertureShape,
+        #[serde(default)]
+        cat_eye: f64,
+        #[serde(default = "default_shutter")]
+        shutter: (f64, f64),
+    },
+    /// Traces rays through a system of lens elements, in front of a sensor of
+    /// height `sensor_height` millimeters. The scene is assumed to be in meters.
+    LensSystem {
+        origin: Vector,
+        look_at: Vector,
+        up_vector: Vector,
+        aspect_ratio: AspectRatio,
+        lens: Lens,
+        #[serde(default = "default_sensor_height")]
+        sensor_height: f64,
+        #[serde(default)]
+        focus_distance: f64,
+        /// Diameter of the aperture stop in millimeters, 0 to keep the one of the lens
+        #[serde(default)]
+        stop_diameter: f64,
+        /// Dispersion of the glass, 0 to disable chromatic aberration
+        #[serde(default)]
+        abbe_number: f64,
+        #[serde(default = "default_shutter")]
+        shutter: (f64, f64),
+    },
+    /// Parallel projection, showing a region `view_height` units high
+    Orthographic {
+        origin: Vector,
+        look_at: Vector,
+        up_vector: Vector,
+        aspect_ratio: AspectRatio,
+        view_height: f64,
+        #[serde(default = "default_shutter")]
+        shutter: (f64, f64),
     },
+    /// Deprecated: an orthographic camera whose view height is `2 * tan(vertical_fov / 2)`
     Isomorphic {
         origin: Vector,
         look_at: Vector,
         up_vector: Vector,
         aspect_ratio: AspectRatio,
         vertical_fov: f64,
+        #[serde(default = "default_shutter")]
+        shutter: (f64, f64),
+    },
+    /// Equidistant fisheye, whose image circle spans the height of the image
+    Fisheye {
+        origin: Vector,
+        look_at: Vector,
+        up_vector: Vector,
+        aspect_ratio: AspectRatio,
+        fov: f64,
+        #[serde(default = "default_shutter")]
+        shutter: (f64, f64),
     },
+    /// 360° panorama, best used with a 2:1 aspect ratio
+    Equirectangular {
+        origin: Vector,
+        look_at: Vector,
+        up_vector: Vector,
+        aspect_ratio: AspectRatio,
+        #[serde(default = "default_shutter")]
+        shutter: (f64, f64),
+    },
+    /// Panorama around the up vector, covering `horizontal_fov` degrees
+    Cylindrical {
+        origin: Vector,
+        look_at: Vector,
+        up_vector: Vector,
+        aspect_ratio: AspectRatio,
+        #[serde(default = "default_horizontal_fov")]
+        horizontal_fov: f64,
+        #[serde(default = "default_shutter")]
+        shutter: (f64, f64),
+    },
+    /// Renders both eyes of `camera` into one image. The aspect ratio of
+    /// `camera` is the one of a single eye.
+    Stereo {
+        camera: Box<Camera>,
+        interocular_distance: f64,
+        #[serde(default)]
+        convergence: f64,
+        #[serde(default)]
+        layout: StereoLayout,
+        #[serde(default)]
+        omnidirectional: bool,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub enum StereoLayout {
+    #[default]
+    SideBySide,
+    TopBottom,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Lens {
+    DoubleGauss50mm,
+    WideAngle22mm,
+    Fisheye10mm,
+    Telephoto250mm,
+    /// Rows of a lens prescription table, from the front of the lens to the film:
+    /// (curvature radius, thickness, refractive index, aperture diameter) in millimeters.
+    /// A null radius is the aperture stop, and a null refractive index is air.
+    Custom(Vec<(f64, f64, f64, f64)>),
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub enum ApertureShape {
+    #[default]
+    Circle,
+    /// `rotation` is in degrees
+    Polygon {
+        blades: u32,
+        #[serde(default)]
+        rotation: f64,
+    },
+    /// A grayscale image, where white is fully open
+    Mask { image: PathBuf },
+}
+
+/// The height of a full frame sensor, in millimeters
+pub fn default_sensor_height() -> f64 {
+    24.0
+}
+
+pub fn default_horizontal_fov() -> f64 {
+    360.0
+}
+
+/// Moving objects go from their start to their end state between times 0 and 1
+pub fn default_shutter() -> (f64, f64) {
+    (0.0, 1.0)
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -112,4 +453,142 @@ pub enum Object {
         density: f64,
         material: String,
     },
+    Cylinder {
+        base: Vector,
+        top: Vector,
+        radius: f64,
+        #[serde(default)]
+        open: bool,
+        material: String,
+    },
+    Cone {
+        base: Vector,
+        apex: Vector,
+        radius: f64,
+        #[serde(default)]
+        open: bool,
+        material: String,
+    },
+    Disk {
+        center: Vector,
+        normal: Vector,
+        radius: f64,
+        material: String,
+    },
+    Torus {
+        center: Vector,
+        axis: Vector,
+        major_radius: f64,
+        minor_radius: f64,
+        material: String,
+    },
+    Plane {
+        point: Vector,
+        normal: Vector,
+        material: String,
+    },
+    Sdf {
+        shape: DistanceField,
+        bounds: (Vector, Vector),
+        material: String,
+    },
+    Heightfield {
+        image: PathBuf,
+        #[serde(default)]
+        origin: Vector,
+        size: (f64, f64),
+        height_scale: f64,
+        material: String,
+    },
+    MovingSphere {
+        start: Vector,
+        end: Vector,
+        radius: f64,
+        material: String,
+    },
+    Instance {
+        object: Box<Object>,
+        keyframes: Vec<Keyframe>,
+    },
+    Union {
+        left: Box<Object>,
+        right: Box<Object>,
+    },
+    Intersection {
+        left: Box<Object>,
+        right: Box<Object>,
+    },
+    Difference {
+        left: Box<Object>,
+        right: Box<Object>,
+    },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Keyframe {
+    pub time: f64,
+    #[serde(default)]
+    pub translation: Vector,
+    /// Euler angles in degrees, applied around the x, y then z axes
+    #[serde(default)]
+    pub rotation: Vector,
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum DistanceField {
+    Sphere {
+        center: Vector,
+        radius: f64,
+    },
+    Box {
+        center: Vector,
+        half_extents: Vector,
+    },
+    RoundBox {
+        center: Vector,
+        half_extents: Vector,
+        radius: f64,
+    },
+    Torus {
+        center: Vector,
+        major_radius: f64,
+        minor_radius: f64,
+    },
+    Capsule {
+        a: Vector,
+        b: Vector,
+        radius: f64,
+    },
+    Mandelbulb {
+        center: Vector,
+        scale: f64,
+        power: f64,
+        iterations: u32,
+    },
+    Union {
+        left: Box<DistanceField>,
+        right: Box<DistanceField>,
+        #[serde(default)]
+        smoothness: f64,
+    },
+    Subtraction {
+        left: Box<DistanceField>,
+        right: Box<DistanceField>,
+        #[serde(default)]
+        smoothness: f64,
+    },
+    Repetition {
+        period: Vector,
+        shape: Box<DistanceField>,
+    },
+    Twist {
+        amount: f64,
+        shape: Box<DistanceField>,
+    },
 }